@@ -4,8 +4,8 @@ use rand_pcg::Mcg128Xsl64;
 use criterion::{criterion_group, criterion_main, Criterion};
 
 use reversi::{
-    search::{RandomFullSearch, RandomSearch},
-    Game, Side,
+    search::{ExactSolver, MoveOrdering, RandomFullSearch, RandomSearch},
+    ActionResult, BitBoard, Game, Side,
 };
 
 fn bit(c: &mut Criterion) {
@@ -27,16 +27,8 @@ fn bit_full10(c: &mut Criterion) {
     c.bench_function("bit_full10", |b| {
         b.iter(|| {
             let mut game = Game::new(
-                RandomFullSearch::new(
-                    Side::Black,
-                    Mcg128Xsl64::from_rng(&mut rng).unwrap(),
-                    64 - 10,
-                ),
-                RandomFullSearch::new(
-                    Side::White,
-                    Mcg128Xsl64::from_rng(&mut rng).unwrap(),
-                    64 - 10,
-                ),
+                RandomFullSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap(), 64 - 10),
+                RandomFullSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap(), 64 - 10),
             );
             let (b, w) = game.play_game();
             assert!(b + w <= 64);
@@ -44,5 +36,125 @@ fn bit_full10(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, bit, bit_full10);
+fn bit_full10_tt(c: &mut Criterion) {
+    let mut rng = Mcg128Xsl64::new(1);
+    c.bench_function("bit_full10_tt", |b| {
+        b.iter(|| {
+            let mut game = Game::new(
+                RandomFullSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap(), 64 - 10)
+                    .with_solver(ExactSolver::new(1 << 16)),
+                RandomFullSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap(), 64 - 10)
+                    .with_solver(ExactSolver::new(1 << 16)),
+            );
+            let (b, w) = game.play_game();
+            assert!(b + w <= 64);
+        });
+    });
+}
+
+fn bit_full10_ordered(c: &mut Criterion) {
+    let mut rng = Mcg128Xsl64::new(1);
+    c.bench_function("bit_full10_ordered", |b| {
+        b.iter(|| {
+            let mut game = Game::new(
+                RandomFullSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap(), 64 - 10)
+                    .with_solver(ExactSolver::new(1 << 16).with_ordering(MoveOrdering::ALL)),
+                RandomFullSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap(), 64 - 10)
+                    .with_solver(ExactSolver::new(1 << 16).with_ordering(MoveOrdering::ALL)),
+            );
+            let (b, w) = game.play_game();
+            assert!(b + w <= 64);
+        });
+    });
+}
+
+/// Ten random positions with 14 empties
+fn endgame_positions() -> Vec<(BitBoard, Side, bool)> {
+    let mut rng = Mcg128Xsl64::new(1);
+    let mut positions = Vec::new();
+    while positions.len() < 10 {
+        let mut game = Game::new(
+            RandomSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap()),
+            RandomSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap()),
+        );
+        loop {
+            let (b, w) = game.board().count();
+            if b + w >= 64 - 14 {
+                positions.push((game.board().clone(), game.side(), game.last_passed()));
+                break;
+            }
+            if let ActionResult::GameSet(_, _) = game.play_one_turn() {
+                break;
+            }
+        }
+    }
+    positions
+}
+
+/// Solve the same 14 empties positions with each move ordering
+fn exact14_ordering(c: &mut Criterion) {
+    let positions = endgame_positions();
+    let orderings = [
+        ("none", MoveOrdering::NONE),
+        (
+            "fastest_first",
+            MoveOrdering {
+                fastest_first: true,
+                ..MoveOrdering::NONE
+            },
+        ),
+        (
+            "corners_parity",
+            MoveOrdering {
+                corners: true,
+                parity: true,
+                ..MoveOrdering::NONE
+            },
+        ),
+        ("all", MoveOrdering::ALL),
+    ];
+    for &(name, ordering) in orderings.iter() {
+        let mut solver = ExactSolver::new(1 << 16).with_ordering(ordering);
+        c.bench_function(&format!("exact14_{}", name), |b| {
+            b.iter(|| {
+                for (board, side, passed) in positions.iter() {
+                    solver.clear();
+                    solver.solve(board, *side, *passed);
+                }
+            });
+        });
+    }
+}
+
+/// Exact solve against the win/loss/draw solve of the same positions
+fn exact14_wld(c: &mut Criterion) {
+    let positions = endgame_positions();
+    let mut solver = ExactSolver::new(1 << 16).with_ordering(MoveOrdering::ALL);
+    c.bench_function("exact14_solve", |b| {
+        b.iter(|| {
+            for (board, side, passed) in positions.iter() {
+                solver.clear();
+                solver.solve(board, *side, *passed);
+            }
+        });
+    });
+    c.bench_function("exact14_solve_wld", |b| {
+        b.iter(|| {
+            for (board, side, passed) in positions.iter() {
+                solver.clear();
+                solver.solve_wld(board, *side, *passed);
+            }
+        });
+    });
+}
+
+criterion_group!(
+    benches,
+    bit,
+    bit_full10,
+    bit_full10_tt,
+    bit_full10_ordered,
+    exact14_ordering,
+    exact14_wld
+);
 criterion_main!(benches);
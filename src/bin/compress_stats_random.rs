@@ -20,12 +20,12 @@ fn main() {
         let w = words.next().unwrap().parse::<u8>().unwrap();
         *counts[i][j].entry((b, w)).or_default() += 1;
     }
-    for i in 0..=16 {
-        for j in 0..=16 {
-            let mut keys = counts[i][j].keys().collect::<Vec<_>>();
+    for (i, row) in counts.iter().enumerate() {
+        for (j, count) in row.iter().enumerate() {
+            let mut keys = count.keys().collect::<Vec<_>>();
             keys.sort();
             for &(b, w) in keys {
-                println!("{} {} {} {} {}", i, j, b, w, counts[i][j][&(b, w)]);
+                println!("{} {} {} {} {}", i, j, b, w, count[&(b, w)]);
             }
         }
     }
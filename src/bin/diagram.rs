@@ -0,0 +1,111 @@
+//! Draw a game given as a transcript as SVG on stdout
+//!
+//! ```text
+//! diagram [--style final|numbers|animate] [--candidates yes|no] [--frame MS]
+//!         [--size PX] TRANSCRIPT
+//! ```
+//!
+//! `final` draws the last position with the last move marked, `numbers`
+//! overlays the move numbers on it and `animate` plays the whole game.
+//! `--candidates` marks the legal moves in the last position.
+
+use std::{env, process, time::Duration};
+
+use reversi::{
+    notation::{parse_transcript, replay_plies},
+    render::{Svg, SvgAnimation},
+    BitBoard, Side,
+};
+
+const USAGE: &str = "usage: diagram [--style final|numbers|animate] [--candidates yes|no] \
+[--frame MS] [--size PX] TRANSCRIPT";
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Style {
+    Final,
+    Numbers,
+    Animate,
+}
+
+struct Options {
+    style: Style,
+    candidates: bool,
+    frame: Duration,
+    size: f64,
+    transcript: String,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        style: Style::Final,
+        candidates: false,
+        frame: Duration::from_secs(1),
+        size: 40.0,
+        transcript: String::new(),
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            options.transcript.push_str(&arg);
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {}", arg))?;
+        let invalid = || format!("invalid value for {}: {}", arg, value);
+        match arg.as_str() {
+            "--style" => {
+                options.style = match value.as_str() {
+                    "final" => Style::Final,
+                    "numbers" => Style::Numbers,
+                    "animate" => Style::Animate,
+                    _ => return Err(invalid()),
+                }
+            }
+            "--candidates" => {
+                options.candidates = match value.as_str() {
+                    "yes" => true,
+                    "no" => false,
+                    _ => return Err(invalid()),
+                }
+            }
+            "--frame" => {
+                options.frame = Duration::from_millis(value.parse().map_err(|_| invalid())?)
+            }
+            "--size" => options.size = value.parse().map_err(|_| invalid())?,
+            _ => return Err(format!("unknown option: {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
+fn main() {
+    let options = parse_options().unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        process::exit(2);
+    });
+    let (board, side, plies) = parse_transcript(&options.transcript)
+        .and_then(|moves| replay_plies(&BitBoard::new(), Side::Black, &moves))
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        });
+    if options.style == Style::Animate {
+        let animation = SvgAnimation::new(&board, &plies)
+            .with_frame_duration(options.frame)
+            .with_square_size(options.size);
+        print!("{}", animation);
+        return;
+    }
+    let mut svg = Svg::new(&board).with_square_size(options.size);
+    if let Some(ply) = plies.last() {
+        svg = svg.with_last_move(ply);
+    }
+    if options.style == Style::Numbers {
+        svg = svg.with_move_numbers(&plies);
+    }
+    if options.candidates {
+        svg = svg.with_candidates(side);
+    }
+    print!("{}", svg);
+}
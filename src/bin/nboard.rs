@@ -0,0 +1,70 @@
+//! NBoard protocol engine on stdin/stdout
+//!
+//! ```text
+//! nboard [--weights PATH] [--exact N]
+//! ```
+//!
+//! `--weights` evaluates with a `PatternEvaluator` instead of the simple
+//! evaluator, and `--exact` sets the number of empties from which positions
+//! are solved exactly.
+
+use std::{
+    env,
+    io::{self, BufRead, Write},
+    process,
+    sync::Arc,
+};
+
+use reversi::{nboard::Engine, search::PatternEvaluator};
+
+const USAGE: &str = "usage: nboard [--weights PATH] [--exact N]";
+
+fn parse_engine() -> Result<Engine, String> {
+    let mut engine = Engine::new();
+    let mut args = env::args().skip(1);
+    while let Some(key) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {}", key))?;
+        match key.as_str() {
+            "--weights" => {
+                let evaluator = PatternEvaluator::load_file(&value)
+                    .map_err(|e| format!("failed to load {}: {}", value, e))?;
+                engine = engine.with_evaluator(Arc::new(evaluator));
+            }
+            "--exact" => {
+                let empties = value
+                    .parse()
+                    .map_err(|_| format!("invalid value for {}: {}", key, value))?;
+                engine = engine.with_exact_empties(empties);
+            }
+            _ => return Err(format!("unknown option: {}", key)),
+        }
+    }
+    Ok(engine)
+}
+
+fn main() {
+    let mut engine = parse_engine().unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        process::exit(2);
+    });
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for line in stdin.lock().lines() {
+        let line = line.unwrap_or_else(|e| {
+            eprintln!("failed to read stdin: {}", e);
+            process::exit(1);
+        });
+        match engine.handle(&line, &mut out) {
+            Ok(true) => (),
+            Ok(false) => break,
+            Err(e) => eprintln!("{}", e),
+        }
+        if let Err(e) = out.flush() {
+            eprintln!("failed to write stdout: {}", e);
+            process::exit(1);
+        }
+    }
+}
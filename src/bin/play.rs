@@ -0,0 +1,256 @@
+//! Play against a searcher in the terminal
+//!
+//! ```text
+//! play [--side black|white] [--hint SPEC] [--seed N]
+//!      [--colour auto|always|never] [SPEC]
+//! ```
+//!
+//! `SPEC` is the opponent (`alphabeta:4` by default) and `--hint` the
+//! searcher asked for hints; see `SearcherSpec`. Moves are entered as
+//! `f5`, and `undo`, `hint`, `help` and `quit` are also understood. The
+//! board is coloured when stdout is a terminal and `NO_COLOR` is unset,
+//! unless `--colour` says otherwise.
+
+use std::{
+    env,
+    io::{self, BufRead, IsTerminal, Write},
+    process,
+};
+
+use reversi::{
+    notation::{parse_square, square_name, Move},
+    render::Render,
+    search::{Evaluation, Occupied, Search, SearchResult, SearcherSpec, Wld, DISC},
+    ActionResult, BitBoard, Candidate, Game, Side, Undo,
+};
+
+const USAGE: &str = "usage: play [--side black|white] [--hint SPEC] [--seed N] \
+[--colour auto|always|never] [SPEC]";
+
+const HELP: &str = "commands: a1..h8 to move, undo, hint, help, quit";
+
+struct Options {
+    side: Side,
+    opponent: SearcherSpec,
+    hint: SearcherSpec,
+    seed: u128,
+    colour: bool,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        side: Side::Black,
+        opponent: SearcherSpec::AlphaBeta { depth: 4 },
+        hint: SearcherSpec::AlphaBeta { depth: 6 },
+        seed: 1,
+        colour: io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none(),
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            options.opponent = arg.parse().map_err(|e| format!("{}", e))?;
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {}", arg))?;
+        let invalid = || format!("invalid value for {}: {}", arg, value);
+        match arg.as_str() {
+            "--side" => {
+                options.side = match value.as_str() {
+                    "black" => Side::Black,
+                    "white" => Side::White,
+                    _ => return Err(invalid()),
+                }
+            }
+            "--hint" => options.hint = value.parse().map_err(|e| format!("{}", e))?,
+            "--seed" => options.seed = value.parse().map_err(|_| invalid())?,
+            "--colour" => {
+                options.colour = match value.as_str() {
+                    "auto" => options.colour,
+                    "always" => true,
+                    "never" => false,
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => return Err(format!("unknown option: {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
+/// Stand-in searcher for the human, whose moves go through `Game::play_move`
+struct Human;
+
+impl Search for Human {
+    fn search(
+        &mut self,
+        _board: &BitBoard,
+        _side: Side,
+        _occupied: Occupied,
+        _candidates: &mut Candidate,
+        _last_passed: bool,
+    ) -> SearchResult {
+        unreachable!("human moves are entered with Game::play_move")
+    }
+}
+
+type Player = Box<dyn Search + Send>;
+
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::Black => "black",
+        Side::White => "white",
+    }
+}
+
+fn describe(score: Option<Evaluation>) -> String {
+    match score {
+        Some(Evaluation::Exact(c)) => format!("{:+} exact", c.mine as i32 - c.opp as i32),
+        Some(Evaluation::Wld(Wld::Win)) => "win".to_string(),
+        Some(Evaluation::Wld(Wld::Draw)) => "draw".to_string(),
+        Some(Evaluation::Wld(Wld::Loss)) => "loss".to_string(),
+        Some(Evaluation::Estimate(e)) => format!("{:+.1}", e.0 as f64 / DISC as f64),
+        None => "no score".to_string(),
+    }
+}
+
+fn show<W: Write>(
+    game: &Game<Player, Player>,
+    human: Side,
+    colour: bool,
+    out: &mut W,
+) -> io::Result<()> {
+    let mut render = Render::new(game.board()).with_colour(colour);
+    if game.side() == human {
+        render = render.with_candidates(human);
+    }
+    if let Some(ply) = game.history().last() {
+        render = render.with_last_move(ply);
+    }
+    write!(out, "\n{}", render)?;
+    let (b, w) = game.board().count();
+    writeln!(out, "● {} ○ {}", b, w)
+}
+
+fn announce<W: Write>(action: ActionResult, out: &mut W) -> io::Result<()> {
+    match action {
+        ActionResult::Put(side, col, row) => writeln!(
+            out,
+            "{} plays {}",
+            side_name(side),
+            square_name(BitBoard::position(col, row))
+        ),
+        ActionResult::Pass(side) => writeln!(out, "{} passes", side_name(side)),
+        ActionResult::GameSet(b, w) => {
+            let result = match b.cmp(&w) {
+                std::cmp::Ordering::Greater => "black wins",
+                std::cmp::Ordering::Less => "white wins",
+                std::cmp::Ordering::Equal => "draw",
+            };
+            writeln!(out, "game over: {} ({}-{})", result, b, w)
+        }
+        ActionResult::TimeLoss(side) => writeln!(out, "{} lost on time", side_name(side)),
+    }
+}
+
+fn main() {
+    let options = parse_options().unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        process::exit(2);
+    });
+    let human = options.side;
+    let opponent = options.opponent.build(options.seed);
+    let mut hint = options.hint.build(options.seed.wrapping_add(1));
+    let mut game: Game<Player, Player> = match human {
+        Side::Black => Game::new(Box::new(Human), opponent),
+        Side::White => Game::new(opponent, Box::new(Human)),
+    };
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut lines = stdin.lock().lines();
+    let mut result = writeln!(
+        out,
+        "you play {} against {}\n{}",
+        side_name(human),
+        options.opponent,
+        HELP
+    );
+    let mut over = false;
+    while result.is_ok() {
+        result = (|| {
+            // 相手の手番とパスは自動で進める
+            while !over && (game.side() != human || game.board().candidates(human).len() == 0) {
+                let action = game.play_one_turn();
+                over = matches!(action, ActionResult::GameSet(..));
+                announce(action, &mut out)?;
+            }
+            show(&game, human, options.colour, &mut out)?;
+            if !over {
+                write!(out, "{}> ", side_name(human))?;
+            }
+            out.flush()
+        })();
+        if result.is_err() || over {
+            break;
+        }
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => break,
+        };
+        let command = line.trim();
+        result = match command {
+            "" => Ok(()),
+            "quit" | "q" => break,
+            "help" | "?" => writeln!(out, "{}", HELP),
+            "undo" | "u" => {
+                let moved = game
+                    .history()
+                    .iter()
+                    .any(|p| p.side == human && p.mv != Move::Pass);
+                if moved {
+                    // 相手の応手ごと戻して自分の手番にする
+                    while let Some(undo) = game.undo() {
+                        if let Undo::Ply(ply) = undo {
+                            if ply.side == human && ply.mv != Move::Pass {
+                                break;
+                            }
+                        }
+                    }
+                    Ok(())
+                } else {
+                    writeln!(out, "nothing to undo")
+                }
+            }
+            "hint" | "h" => {
+                let board = game.board().clone();
+                let (b, w) = board.count();
+                let result = hint.search(
+                    &board,
+                    human,
+                    b + w,
+                    &mut board.candidates(human),
+                    game.last_passed(),
+                );
+                writeln!(
+                    out,
+                    "hint: {} ({})",
+                    square_name(result.position),
+                    describe(result.score)
+                )
+            }
+            _ => match parse_square(command) {
+                Some(position) => match game.play_move(position) {
+                    Ok(action) => announce(action, &mut out),
+                    Err(_) => writeln!(out, "{} is not a legal move", command),
+                },
+                None => writeln!(out, "unknown command: {}\n{}", command, HELP),
+            },
+        };
+    }
+    if let Err(e) = result {
+        eprintln!("failed to write stdout: {}", e);
+        process::exit(1);
+    }
+}
@@ -1,7 +1,7 @@
 use rand::SeedableRng;
 use rand_pcg::Mcg128Xsl64;
 
-use reversi::{search::RandomFullSearch, Game, Side};
+use reversi::{search::RandomFullSearch, Game};
 
 fn main() {
     let mut rng = Mcg128Xsl64::from_entropy();
@@ -9,16 +9,8 @@ fn main() {
         for i in 0..=16 {
             for j in 0..=16 {
                 let (b, w) = Game::new(
-                    RandomFullSearch::new(
-                        Side::Black,
-                        Mcg128Xsl64::from_rng(&mut rng).unwrap(),
-                        64 - i,
-                    ),
-                    RandomFullSearch::new(
-                        Side::White,
-                        Mcg128Xsl64::from_rng(&mut rng).unwrap(),
-                        64 - j,
-                    ),
+                    RandomFullSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap(), 64 - i),
+                    RandomFullSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap(), 64 - j),
                 )
                 .play_game();
                 println!("{} {} {} {}", i, j, b, w);
@@ -0,0 +1,315 @@
+//! Play matches between searchers and estimate their relative strength
+//!
+//! ```text
+//! tournament [--mode round-robin|gauntlet] [--games N] [--openings PATH]
+//!            [--opening-plies N] [--threads N] [--time MS] [--seed N] SPEC...
+//! ```
+//!
+//! Every pair plays each opening twice with the colours swapped. Openings
+//! are read from a file with one `f5d6c3` transcript per line, or generated
+//! by playing `--opening-plies` random moves. In a gauntlet the first
+//! searcher meets every other one. See `SearcherSpec` for the specs.
+
+use std::{
+    env,
+    fs::File,
+    io::{BufRead, BufReader},
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use rand::{Rng, SeedableRng};
+use rand_pcg::Mcg128Xsl64;
+
+use reversi::{
+    notation::replay_transcript,
+    search::{RandomSearch, Score, SearcherSpec, Wld},
+    ActionResult, BitBoard, Game, Record, Side, TimeControl,
+};
+
+const USAGE: &str = "usage: tournament [--mode round-robin|gauntlet] [--games N] \
+[--openings PATH] [--opening-plies N] [--threads N] [--time MS] [--seed N] SPEC...";
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Mode {
+    RoundRobin,
+    Gauntlet,
+}
+
+struct Options {
+    mode: Mode,
+    /// Openings per pairing, each played with both colours
+    games: usize,
+    openings: Option<String>,
+    opening_plies: usize,
+    threads: usize,
+    time: Option<Duration>,
+    seed: u128,
+    players: Vec<SearcherSpec>,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        mode: Mode::RoundRobin,
+        games: 10,
+        openings: None,
+        opening_plies: 6,
+        threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        time: None,
+        seed: 1,
+        players: Vec::new(),
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            let spec = arg.parse().map_err(|e| format!("{}", e))?;
+            options.players.push(spec);
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {}", arg))?;
+        let invalid = || format!("invalid value for {}: {}", arg, value);
+        match arg.as_str() {
+            "--mode" => {
+                options.mode = match value.as_str() {
+                    "round-robin" => Mode::RoundRobin,
+                    "gauntlet" => Mode::Gauntlet,
+                    _ => return Err(invalid()),
+                }
+            }
+            "--games" => options.games = value.parse().map_err(|_| invalid())?,
+            "--openings" => options.openings = Some(value),
+            "--opening-plies" => options.opening_plies = value.parse().map_err(|_| invalid())?,
+            "--threads" => options.threads = value.parse().map_err(|_| invalid())?,
+            "--time" => {
+                options.time = Some(Duration::from_millis(value.parse().map_err(|_| invalid())?))
+            }
+            "--seed" => options.seed = value.parse().map_err(|_| invalid())?,
+            _ => return Err(format!("unknown option: {}", arg)),
+        }
+    }
+    if options.players.len() < 2 {
+        return Err("at least two searchers are required".to_string());
+    }
+    if options.threads == 0 {
+        return Err("--threads must be positive".to_string());
+    }
+    // 60 手打つと盤が埋まって終局している
+    if options.opening_plies >= 60 {
+        return Err("--opening-plies must be at most 59".to_string());
+    }
+    Ok(options)
+}
+
+type Opening = (BitBoard, Side);
+
+fn load_openings(path: &str, games: usize) -> Result<Vec<Opening>, String> {
+    let file = File::open(path).map_err(|e| format!("failed to open {}: {}", path, e))?;
+    let mut openings = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("failed to read {}: {}", path, e))?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let opening = replay_transcript(line).map_err(|e| format!("{}:{}: {}", path, i + 1, e))?;
+        openings.push(opening);
+    }
+    if openings.is_empty() {
+        return Err(format!("no openings in {}", path));
+    }
+    Ok(openings.into_iter().cycle().take(games).collect())
+}
+
+/// Random openings tried for each one kept before giving up
+const OPENING_ATTEMPTS: usize = 100;
+
+/// Play `plies` random moves, passes not counted, from the initial position
+/// for every game, keeping the positions where the game is not over
+fn random_openings(
+    plies: usize,
+    games: usize,
+    rng: &mut Mcg128Xsl64,
+) -> Result<Vec<Opening>, String> {
+    let mut openings = Vec::with_capacity(games);
+    for _ in 0..games * OPENING_ATTEMPTS {
+        if openings.len() == games {
+            break;
+        }
+        let mut game = Game::new(
+            RandomSearch::new(Mcg128Xsl64::from_rng(&mut *rng).unwrap()),
+            RandomSearch::new(Mcg128Xsl64::from_rng(&mut *rng).unwrap()),
+        );
+        let mut moves = 0;
+        while moves < plies {
+            match game.play_one_turn() {
+                ActionResult::Put(..) => moves += 1,
+                ActionResult::Pass(_) => (),
+                ActionResult::GameSet(..) | ActionResult::TimeLoss(_) => break,
+            }
+        }
+        let (board, side) = (game.board(), game.side());
+        // 手番側がパスするだけの局面も対局できる
+        if moves == plies
+            && (board.candidates(side).len() > 0 || board.candidates(side.flip()).len() > 0)
+        {
+            openings.push((board.clone(), side));
+        }
+    }
+    if openings.len() < games {
+        return Err(format!("no playable position after {} random moves", plies));
+    }
+    Ok(openings)
+}
+
+struct Job {
+    first: usize,
+    second: usize,
+    opening: usize,
+    /// Whether the first player has the first move of the opening
+    first_moves: bool,
+    seed: u128,
+}
+
+fn play(options: &Options, openings: &[Opening], job: &Job) -> Wld {
+    let (board, side) = openings[job.opening].clone();
+    let first = options.players[job.first].build(job.seed);
+    let second = options.players[job.second].build(job.seed.wrapping_add(1));
+    let first_side = if job.first_moves { side } else { side.flip() };
+    let (black, white) = match first_side {
+        Side::Black => (first, second),
+        Side::White => (second, first),
+    };
+    let mut game = Game::with_position(board, side, false, black, white);
+    if let Some(time) = options.time {
+        for &side in [Side::Black, Side::White].iter() {
+            game = game.with_time_control(side, TimeControl::PerMove(time));
+        }
+    }
+    let (b, w) = game.play_game();
+    match game.time_loss() {
+        Some(loser) if loser == first_side => Wld::Loss,
+        Some(_) => Wld::Win,
+        None => match first_side {
+            Side::Black => Wld::from_counts(b as u32, w as u32),
+            Side::White => Wld::from_counts(w as u32, b as u32),
+        },
+    }
+}
+
+fn main() {
+    let options = parse_options().unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        process::exit(2);
+    });
+    let mut rng = Mcg128Xsl64::new(options.seed);
+    let openings = match &options.openings {
+        Some(path) => load_openings(path, options.games).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        }),
+        None => {
+            random_openings(options.opening_plies, options.games, &mut rng).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                process::exit(1);
+            })
+        }
+    };
+    let n = options.players.len();
+    let pairs = match options.mode {
+        Mode::RoundRobin => (0..n)
+            .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+            .collect::<Vec<_>>(),
+        Mode::Gauntlet => (1..n).map(|j| (0, j)).collect(),
+    };
+    let mut jobs = Vec::new();
+    for &(first, second) in pairs.iter() {
+        for opening in 0..openings.len() {
+            for &first_moves in [true, false].iter() {
+                jobs.push(Job {
+                    first,
+                    second,
+                    opening,
+                    first_moves,
+                    seed: rng.gen(),
+                });
+            }
+        }
+    }
+
+    let records = Mutex::new(vec![vec![Record::default(); n]; n]);
+    let next = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..options.threads.min(jobs.len()) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let job = match jobs.get(i) {
+                    Some(job) => job,
+                    None => break,
+                };
+                let wld = play(&options, &openings, job);
+                let mut records = records.lock().unwrap();
+                records[job.first][job.second].add(wld);
+                records[job.second][job.first].add(wld.flip());
+                let done = records.iter().flatten().map(|r| r.games()).sum::<u32>() / 2;
+                eprint!("\r{}/{} games", done, jobs.len());
+            });
+        }
+    });
+    eprintln!();
+
+    let records = records.into_inner().unwrap();
+    let names = options
+        .players
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>();
+    let width = names.iter().map(|s| s.len()).max().unwrap().max(6);
+    println!(
+        "{:>w$}  {:>5} {:>5} {:>5}  {:>6}  {:>21}",
+        "",
+        "W",
+        "D",
+        "L",
+        "score",
+        "Elo (95%)",
+        w = width
+    );
+    for (i, name) in names.iter().enumerate() {
+        let mut total = Record::default();
+        for &r in records[i].iter() {
+            total.merge(r);
+        }
+        if total.games() == 0 {
+            continue;
+        }
+        let (e, low, high) = total.elo();
+        println!(
+            "{:>w$}  {:>5} {:>5} {:>5}  {:>5.1}%  {:>+6.0} [{:>+6.0}, {:>+6.0}]",
+            name,
+            total.wins,
+            total.draws,
+            total.losses,
+            total.score() * 100.0,
+            e,
+            low,
+            high,
+            w = width
+        );
+    }
+    println!();
+    for &(i, j) in pairs.iter() {
+        let r = records[i][j];
+        let (e, low, high) = r.elo();
+        println!(
+            "{} vs {}: +{} ={} -{}  {:+.0} [{:+.0}, {:+.0}]",
+            names[i], names[j], r.wins, r.draws, r.losses, e, low, high
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! Fit the weights of `PatternEvaluator` on random positions
+//!
+//! Positions are taken from random games once at most `--empties` squares
+//! are left and labelled with the final disc differential of perfect play
+//! found by `ExactSolver`, so only the phases covering those positions are
+//! trained.
+
+use std::{env, process};
+
+use rand_pcg::Mcg128Xsl64;
+
+use reversi::search::{exact_samples, PatternEvaluator};
+
+const USAGE: &str = "usage: train_pattern [--games N] [--empties N] [--phases N] \
+[--epochs N] [--rate R] [--seed N] [--output PATH]";
+
+struct Options {
+    games: usize,
+    empties: u8,
+    phases: usize,
+    epochs: usize,
+    rate: f32,
+    seed: u128,
+    output: String,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        games: 1000,
+        empties: 12,
+        phases: 4,
+        epochs: 20,
+        rate: 0.002,
+        seed: 1,
+        output: "pattern.bin".to_string(),
+    };
+    let mut args = env::args().skip(1);
+    while let Some(key) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {}", key))?;
+        let invalid = || format!("invalid value for {}: {}", key, value);
+        match key.as_str() {
+            "--games" => options.games = value.parse().map_err(|_| invalid())?,
+            "--empties" => options.empties = value.parse().map_err(|_| invalid())?,
+            "--phases" => options.phases = value.parse().map_err(|_| invalid())?,
+            "--epochs" => options.epochs = value.parse().map_err(|_| invalid())?,
+            "--rate" => options.rate = value.parse().map_err(|_| invalid())?,
+            "--seed" => options.seed = value.parse().map_err(|_| invalid())?,
+            "--output" => options.output = value,
+            _ => return Err(format!("unknown option: {}", key)),
+        }
+    }
+    if !(1..=61).contains(&options.phases) {
+        return Err("--phases must be in 1..=61".to_string());
+    }
+    if options.empties > 60 {
+        return Err("--empties must be at most 60".to_string());
+    }
+    Ok(options)
+}
+
+fn main() {
+    let options = parse_options().unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        process::exit(2);
+    });
+    let mut rng = Mcg128Xsl64::new(options.seed);
+    let mut evaluator = PatternEvaluator::new(options.phases);
+    let samples = exact_samples(&evaluator, options.games, options.empties, &mut rng);
+    eprintln!("{} positions from {} games", samples.len(), options.games);
+    for i in 0..options.epochs {
+        let mse = evaluator.fit_epoch(&samples, options.rate, &mut rng);
+        eprintln!("epoch {}: mse {:.3}", i + 1, mse);
+    }
+    if let Err(e) = evaluator.save_file(&options.output) {
+        eprintln!("failed to write {}: {}", options.output, e);
+        process::exit(1);
+    }
+}
@@ -1,4 +1,6 @@
-use std::io::Write;
+use std::{error::Error, fmt, io::Write, str::FromStr};
+
+use crate::render::Render;
 
 pub const SIZE: usize = 8;
 pub type Count = u8;
@@ -28,7 +30,8 @@ impl Side {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+/// `{:?}` shows the board on one line and `{:#?}` as a labelled grid
+#[derive(Clone, Eq, PartialEq, Hash)]
 pub struct BitBoard {
     black: u64,
     white: u64,
@@ -41,6 +44,26 @@ impl BitBoard {
             white: 0x0000001008000000,
         }
     }
+
+    /// Build a board from raw masks, where the most significant bit is `a1`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `black` and `white` overlap.
+    pub fn from_masks(black: u64, white: u64) -> BitBoard {
+        assert_eq!(black & white, 0, "black and white discs overlap");
+        BitBoard { black, white }
+    }
+
+    #[inline]
+    pub const fn black(&self) -> u64 {
+        self.black
+    }
+
+    #[inline]
+    pub const fn white(&self) -> u64 {
+        self.white
+    }
 }
 
 impl Default for BitBoard {
@@ -109,6 +132,12 @@ impl BitBoard {
     }
 
     pub fn put(&mut self, side: Side, position: Position) {
+        let flips = self.flips(side, position);
+        self.make(side, position, flips);
+    }
+
+    /// Discs flipped when `side` puts on `position`, without touching the board
+    pub fn flips(&self, side: Side, position: Position) -> u64 {
         let mut rev = 0;
         let (mine, opp) = self.mine_opp_keys(side);
         for &(shift, mask) in [
@@ -147,18 +176,63 @@ impl BitBoard {
                 rev |= tmp;
             }
         }
+        rev
+    }
+
+    /// Apply a move whose flipped discs were computed by [`BitBoard::flips`]
+    #[inline]
+    pub fn make(&mut self, side: Side, position: Position, flips: u64) {
         match side {
             Side::Black => {
-                self.black ^= rev | position;
-                self.white ^= rev;
+                self.black ^= flips | position;
+                self.white ^= flips;
             }
             Side::White => {
-                self.black ^= rev;
-                self.white ^= rev | position;
+                self.black ^= flips;
+                self.white ^= flips | position;
             }
         }
     }
 
+    /// Revert a move applied by [`BitBoard::make`] with the same arguments
+    #[inline]
+    pub fn unmake(&mut self, side: Side, position: Position, flips: u64) {
+        match side {
+            Side::Black => {
+                self.black ^= flips | position;
+                self.white ^= flips;
+            }
+            Side::White => {
+                self.black ^= flips;
+                self.white ^= flips | position;
+            }
+        }
+    }
+
+    /// Put a disc after checking that `position` is a legal move for `side`
+    ///
+    /// Returns the flipped discs. The board is left untouched when an error
+    /// is returned.
+    pub fn try_put(&mut self, side: Side, position: Position) -> Result<u64, PutError> {
+        let flips = self.legal_flips(side, position)?;
+        self.make(side, position, flips);
+        Ok(flips)
+    }
+
+    /// Like [`BitBoard::flips`], but fails unless `position` is a legal move
+    pub fn legal_flips(&self, side: Side, position: Position) -> Result<u64, PutError> {
+        if position.count_ones() != 1 {
+            return Err(PutError::NotSingleSquare(position));
+        }
+        if (self.black | self.white) & position != 0 {
+            return Err(PutError::Occupied(position));
+        }
+        match self.flips(side, position) {
+            0 => Err(PutError::NoFlips(position)),
+            flips => Ok(flips),
+        }
+    }
+
     #[inline]
     pub fn candidates(&self, side: Side) -> Candidate {
         let (mine, opp) = self.mine_opp_keys(side);
@@ -184,11 +258,40 @@ impl BitBoard {
     }
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum PutError {
+    /// The position is empty or has more than one bit set
+    NotSingleSquare(Position),
+    Occupied(Position),
+    /// The square is empty but flips no disc
+    NoFlips(Position),
+}
+
+impl fmt::Display for PutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PutError::NotSingleSquare(p) => write!(f, "{:#018x} is not a single square", p),
+            PutError::Occupied(p) => write!(f, "{:#018x} is already occupied", p),
+            PutError::NoFlips(p) => write!(f, "{:#018x} flips no disc", p),
+        }
+    }
+}
+
+impl Error for PutError {}
+
 #[derive(Debug)]
 pub struct Candidate {
     pos: u64,
 }
 
+impl Candidate {
+    /// Whether `position` is one of the remaining candidates
+    #[inline]
+    pub fn contains(&self, position: Position) -> bool {
+        position.count_ones() == 1 && self.pos & position != 0
+    }
+}
+
 impl Iterator for Candidate {
     type Item = u64;
     fn next(&mut self) -> Option<u64> {
@@ -213,8 +316,28 @@ impl ExactSizeIterator for Candidate {
     }
 }
 
+impl fmt::Debug for BitBoard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "BitBoard\n{}", Render::new(self))
+        } else {
+            write!(f, "BitBoard({})", Render::new(self).with_compact(true))
+        }
+    }
+}
+
 impl BitBoard {
     pub fn format<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.format_marked(0, writer)
+    }
+
+    /// Like [`BitBoard::format`], with the legal moves of `side` shown as `*`
+    pub fn format_candidates<W: Write>(&self, side: Side, writer: &mut W) -> std::io::Result<()> {
+        let (mine, opp) = self.mine_opp_keys(side);
+        self.format_marked(can_put(mine, opp), writer)
+    }
+
+    fn format_marked<W: Write>(&self, marks: u64, writer: &mut W) -> std::io::Result<()> {
         let mut mask = 0x8000000000000000;
         for _ in 0..8 {
             for _ in 0..8 {
@@ -222,6 +345,8 @@ impl BitBoard {
                     write!(writer, "●")?;
                 } else if self.white & mask != 0 {
                     write!(writer, "○")?;
+                } else if marks & mask != 0 {
+                    write!(writer, "*")?;
                 } else {
                     write!(writer, "_")?;
                 }
@@ -233,30 +358,151 @@ impl BitBoard {
     }
 }
 
+/// A board together with the side to move
+///
+/// The text form is the first line of OBF: 64 squares from `a1` to `h8`
+/// (`X` black, `O` white, `-` empty), a space and the side to move.
+///
+/// ```rust
+/// # use reversi::{BitBoard, Setup, Side};
+/// let s = "---------------------------OX------XO--------------------------- X";
+/// let setup: Setup = s.parse().unwrap();
+/// assert_eq!(setup, Setup::new(BitBoard::new(), Side::Black));
+/// assert_eq!(setup.to_string(), s);
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Setup {
+    pub board: BitBoard,
+    pub side: Side,
+}
+
+impl Setup {
+    pub const fn new(board: BitBoard, side: Side) -> Setup {
+        Setup { board, side }
+    }
+}
+
+impl fmt::Display for Setup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut mask = 0x8000000000000000;
+        while mask != 0 {
+            if self.board.black & mask != 0 {
+                f.write_str("X")?;
+            } else if self.board.white & mask != 0 {
+                f.write_str("O")?;
+            } else {
+                f.write_str("-")?;
+            }
+            mask >>= 1;
+        }
+        match self.side {
+            Side::Black => f.write_str(" X"),
+            Side::White => f.write_str(" O"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ParseSetupError {
+    /// Number of squares + side characters, expected 65
+    Length(usize),
+    InvalidSquare(char),
+    InvalidSide(char),
+}
+
+impl fmt::Display for ParseSetupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseSetupError::Length(n) => {
+                write!(f, "expected 64 squares and a side, got {} chars", n)
+            }
+            ParseSetupError::InvalidSquare(c) => write!(f, "invalid square {:?}", c),
+            ParseSetupError::InvalidSide(c) => write!(f, "invalid side {:?}", c),
+        }
+    }
+}
+
+impl Error for ParseSetupError {}
+
+impl FromStr for Setup {
+    type Err = ParseSetupError;
+
+    /// Accepts `X`/`*`/`●` for black, `O`/`○` for white and `-`/`.`/`_` for
+    /// empty squares; whitespace and anything after `;` are ignored.
+    fn from_str(s: &str) -> Result<Setup, ParseSetupError> {
+        let s = s.split(';').next().unwrap_or_default();
+        let chars = s.chars().filter(|c| !c.is_whitespace()).collect::<Vec<_>>();
+        if chars.len() != 65 {
+            return Err(ParseSetupError::Length(chars.len()));
+        }
+        let mut black = 0;
+        let mut white = 0;
+        let mut mask = 0x8000000000000000;
+        for &c in chars[..64].iter() {
+            match c {
+                'X' | 'x' | '*' | '●' => black |= mask,
+                'O' | 'o' | '○' => white |= mask,
+                '-' | '.' | '_' => (),
+                c => return Err(ParseSetupError::InvalidSquare(c)),
+            }
+            mask >>= 1;
+        }
+        let side = match chars[64] {
+            'X' | 'x' | '*' | 'B' | 'b' => Side::Black,
+            'O' | 'o' | 'W' | 'w' => Side::White,
+            c => return Err(ParseSetupError::InvalidSide(c)),
+        };
+        Ok(Setup::new(BitBoard { black, white }, side))
+    }
+}
+
 impl BitBoard {
     pub fn symmetry(&self, flag: u32) -> BitBoard {
-        let mut sym = self.clone();
+        BitBoard {
+            black: BitBoard::symmetry_mask(self.black, flag),
+            white: BitBoard::symmetry_mask(self.white, flag),
+        }
+    }
+
+    /// Apply the transformation of [`BitBoard::symmetry`] to a single mask
+    pub fn symmetry_mask(mut mask: u64, flag: u32) -> u64 {
         if flag & 1 != 0 {
-            sym.black = horizontal_mirror(sym.black);
-            sym.white = horizontal_mirror(sym.white);
+            mask = horizontal_mirror(mask);
         }
         if flag & 2 != 0 {
-            sym.black = vertical_mirror(sym.black);
-            sym.white = vertical_mirror(sym.white);
+            mask = vertical_mirror(mask);
+        }
+        if flag & 4 != 0 {
+            mask = transpose(mask);
         }
+        mask
+    }
+
+    /// Undo [`BitBoard::symmetry_mask`] with the same `flag`
+    pub fn inverse_symmetry_mask(mut mask: u64, flag: u32) -> u64 {
         if flag & 4 != 0 {
-            sym.black = transpose(sym.black);
-            sym.white = transpose(sym.white);
+            mask = transpose(mask);
         }
-        sym
+        if flag & 2 != 0 {
+            mask = vertical_mirror(mask);
+        }
+        if flag & 1 != 0 {
+            mask = horizontal_mirror(mask);
+        }
+        mask
     }
 
     pub fn unique(&self) -> BitBoard {
-        let mut u = self.clone();
+        self.unique_with_flag().0
+    }
+
+    /// [`BitBoard::unique`] and the `flag` such that `self.symmetry(flag)` is it
+    pub fn unique_with_flag(&self) -> (BitBoard, u32) {
+        let mut u = (self.clone(), 0);
         for f in 1..8 {
             let sym = self.symmetry(f);
-            if (sym.black, sym.white) < (u.black, u.white) {
-                u = sym;
+            if (sym.black, sym.white) < (u.0.black, u.0.white) {
+                u = (sym, f);
             }
         }
         u
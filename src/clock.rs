@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use crate::search::Occupied;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum TimeControl {
+    /// At most this long for every move
+    PerMove(Duration),
+    /// A total for the whole game, plus `increment` after every move
+    Fischer {
+        total: Duration,
+        increment: Duration,
+    },
+}
+
+/// Time left to one player
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Clock {
+    control: TimeControl,
+    remaining: Duration,
+}
+
+impl Clock {
+    pub const fn new(control: TimeControl) -> Clock {
+        let remaining = match control {
+            TimeControl::PerMove(time) => time,
+            TimeControl::Fischer { total, .. } => total,
+        };
+        Clock { control, remaining }
+    }
+
+    pub const fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    /// Time available for the next move before losing on time
+    pub const fn remaining(&self) -> Duration {
+        self.remaining
+    }
+
+    /// Suggested thinking time for a move with `occupied` squares filled
+    ///
+    /// The remaining time is spread over the moves left to this player,
+    /// keeping a margin for the overhead around the search.
+    pub fn allot(&self, occupied: Occupied) -> Duration {
+        match self.control {
+            TimeControl::PerMove(time) => time * 9 / 10,
+            TimeControl::Fischer { increment, .. } => {
+                let moves = (64 - occupied.min(64) as u32).div_ceil(2).max(1);
+                (self.remaining / moves + increment).min(self.remaining / 2)
+            }
+        }
+    }
+
+    /// Charge `elapsed` for one move, returning `false` if the time ran out
+    pub fn spend(&mut self, elapsed: Duration) -> bool {
+        match self.control {
+            TimeControl::PerMove(time) => elapsed <= time,
+            TimeControl::Fischer { increment, .. } => match self.remaining.checked_sub(elapsed) {
+                Some(remaining) => {
+                    self.remaining = remaining + increment;
+                    true
+                }
+                None => {
+                    self.remaining = Duration::ZERO;
+                    false
+                }
+            },
+        }
+    }
+}
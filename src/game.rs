@@ -1,8 +1,13 @@
 use crate::{
-    board::{BitBoard, Count, Side},
+    board::{BitBoard, Count, Position, PutError, Side},
+    clock::{Clock, TimeControl},
+    notation::{format_transcript, Move},
     search::{Occupied, Search},
 };
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    time::{Duration, Instant},
+};
 
 #[derive(Debug)]
 pub struct Game<B, W>
@@ -13,7 +18,15 @@ where
     side: Side,
     occupied: Occupied,
     last_passed: bool,
+    start_passed: bool,
     board: BitBoard,
+    history: Vec<Ply>,
+    /// Plies taken back, with the clock of their side after the move
+    redo: Vec<(Ply, Option<Clock>)>,
+    black_clock: Option<Clock>,
+    white_clock: Option<Clock>,
+    /// The side which lost on time and its clock before the failed move
+    time_loss: Option<(Side, Clock)>,
     black_searcher: B,
     white_searcher: W,
 }
@@ -23,6 +36,42 @@ pub enum ActionResult {
     GameSet(Count, Count),
     Pass(Side),
     Put(Side, usize, usize),
+    /// The side ran out of time; the game is over and its move was not played
+    TimeLoss(Side),
+}
+
+/// A recorded move (or pass) with the discs it flipped
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Ply {
+    pub side: Side,
+    pub mv: Move,
+    pub flips: u64,
+    /// Time spent by the searcher, zero for passes
+    pub elapsed: Duration,
+    /// Clock of `side` before the move, if it has one
+    pub clock: Option<Clock>,
+}
+
+/// A step taken back by [`Game::undo`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Undo {
+    /// A move or pass, which [`Game::redo`] can play again
+    Ply(Ply),
+    /// The loss on time of the side, whose clock is restored to what it was
+    /// before the move which was not played
+    TimeLoss(Side),
+}
+
+impl Ply {
+    pub fn action(&self) -> ActionResult {
+        match self.mv {
+            Move::Put(position) => {
+                let (col, row) = BitBoard::col_row(position);
+                ActionResult::Put(self.side, col, row)
+            }
+            Move::Pass => ActionResult::Pass(self.side),
+        }
+    }
 }
 
 impl<B, W> Game<B, W>
@@ -31,22 +80,182 @@ where
     W: Search,
 {
     pub fn new(black_searcher: B, white_searcher: W) -> Game<B, W> {
+        Game::with_position(
+            BitBoard::new(),
+            Side::Black,
+            false,
+            black_searcher,
+            white_searcher,
+        )
+    }
+
+    /// Start from an arbitrary position
+    ///
+    /// `last_passed` tells whether the previous player has just passed, so
+    /// that the game ends if `side` can not move either.
+    pub fn with_position(
+        board: BitBoard,
+        side: Side,
+        last_passed: bool,
+        black_searcher: B,
+        white_searcher: W,
+    ) -> Game<B, W> {
+        let (b, w) = board.count();
         Game {
-            side: Side::Black,
-            occupied: 4,
-            last_passed: false,
-            board: BitBoard::new(),
+            side,
+            occupied: b + w,
+            last_passed,
+            start_passed: last_passed,
+            board,
+            history: Vec::new(),
+            redo: Vec::new(),
+            black_clock: None,
+            white_clock: None,
+            time_loss: None,
             black_searcher,
             white_searcher,
         }
     }
 
+    /// Give `side` a clock, which is passed to its searcher and ends the
+    /// game when it runs out
+    pub fn with_time_control(mut self, side: Side, control: TimeControl) -> Game<B, W> {
+        *self.clock_mut(side) = Some(Clock::new(control));
+        self
+    }
+
+    pub fn clock(&self, side: Side) -> Option<&Clock> {
+        match side {
+            Side::Black => self.black_clock.as_ref(),
+            Side::White => self.white_clock.as_ref(),
+        }
+    }
+
+    fn clock_mut(&mut self, side: Side) -> &mut Option<Clock> {
+        match side {
+            Side::Black => &mut self.black_clock,
+            Side::White => &mut self.white_clock,
+        }
+    }
+
+    /// The side which lost on time, if any
+    ///
+    /// Once set, [`Game::play_one_turn`] keeps returning
+    /// [`ActionResult::TimeLoss`] until [`Game::undo`] takes it back.
+    pub fn time_loss(&self) -> Option<Side> {
+        self.time_loss.map(|(side, _)| side)
+    }
+
+    pub fn board(&self) -> &BitBoard {
+        &self.board
+    }
+
+    /// Side to move
+    pub fn side(&self) -> Side {
+        self.side
+    }
+
+    pub fn last_passed(&self) -> bool {
+        self.last_passed
+    }
+
+    /// Moves and passes played so far, oldest first
+    pub fn history(&self) -> &[Ply] {
+        &self.history
+    }
+
+    fn apply(&mut self, ply: Ply) {
+        match ply.mv {
+            Move::Put(position) => {
+                self.board.make(ply.side, position, ply.flips);
+                self.occupied += 1;
+                self.last_passed = false;
+            }
+            Move::Pass => self.last_passed = true,
+        }
+        self.side = ply.side.flip();
+        self.history.push(ply);
+    }
+
+    /// Take back the last move or pass, restoring the clock of its side
+    ///
+    /// A loss on time is taken back on its own first, so that the side can
+    /// search its move again.
+    pub fn undo(&mut self) -> Option<Undo> {
+        if let Some((side, clock)) = self.time_loss.take() {
+            *self.clock_mut(side) = Some(clock);
+            return Some(Undo::TimeLoss(side));
+        }
+        let ply = self.history.pop()?;
+        if let Move::Put(position) = ply.mv {
+            self.board.unmake(ply.side, position, ply.flips);
+            self.occupied -= 1;
+        }
+        self.side = ply.side;
+        self.last_passed = match self.history.last() {
+            Some(p) => p.mv == Move::Pass,
+            None => self.start_passed,
+        };
+        let after = std::mem::replace(self.clock_mut(ply.side), ply.clock);
+        self.redo.push((ply, after));
+        Some(Undo::Ply(ply))
+    }
+
+    /// Play again the move taken back by the last [`Game::undo`]
+    ///
+    /// The redo stack is cleared whenever a new move is played. Nothing is
+    /// played after a loss on time.
+    pub fn redo(&mut self) -> Option<Ply> {
+        if self.time_loss.is_some() {
+            return None;
+        }
+        let (ply, clock) = self.redo.pop()?;
+        *self.clock_mut(ply.side) = clock;
+        self.apply(ply);
+        Some(ply)
+    }
+
+    /// Undo or redo until `ply` moves (passes included) have been played
+    ///
+    /// Going back also takes back a loss on time. Returns `false` if the redo
+    /// stack runs out before reaching `ply`.
+    pub fn jump_to(&mut self, ply: usize) -> bool {
+        while self.history.len() > ply {
+            self.undo();
+        }
+        while self.history.len() < ply {
+            if self.redo().is_none() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// The moves played so far in `f5d6c3` notation, passes omitted
+    pub fn transcript(&self) -> String {
+        let moves = self
+            .history
+            .iter()
+            .map(|p| p.mv)
+            .filter(|&mv| mv != Move::Pass)
+            .collect::<Vec<_>>();
+        format_transcript(&moves)
+    }
+
     fn game_set(&self) -> ActionResult {
         let (b, w) = self.board.count();
         ActionResult::GameSet(b, w)
     }
 
+    /// Let the searcher of the side to move play one move
+    ///
+    /// # Panics
+    ///
+    /// Panics if the searcher returns an illegal move.
     pub fn play_one_turn(&mut self) -> ActionResult {
+        if let Some((side, _)) = self.time_loss {
+            return ActionResult::TimeLoss(side);
+        }
         if self.occupied == 64 {
             return self.game_set();
         }
@@ -55,46 +264,100 @@ where
             return if self.last_passed {
                 self.game_set()
             } else {
-                let action = ActionResult::Pass(self.side);
-                self.side = self.side.flip();
-                self.last_passed = true;
-                action
+                self.record(Ply {
+                    side: self.side,
+                    mv: Move::Pass,
+                    flips: 0,
+                    elapsed: Duration::ZERO,
+                    clock: self.clock(self.side).copied(),
+                })
             };
         }
-        let (side, col, row) = match self.side {
-            Side::Black => {
-                let position = self.black_searcher.search(
-                    &self.board,
-                    self.occupied,
-                    &mut candidates,
-                    self.last_passed,
-                );
-                self.board.put(self.side, position);
-                let (col, row) = BitBoard::col_row(position);
-                (Side::Black, col, row)
+        let side = self.side;
+        let clock = self.clock(side).copied();
+        if let Some(clock) = clock {
+            let limit = clock.allot(self.occupied);
+            match side {
+                Side::Black => self.black_searcher.set_time_limit(limit),
+                Side::White => self.white_searcher.set_time_limit(limit),
             }
-            Side::White => {
-                let position = self.white_searcher.search(
-                    &self.board,
-                    self.occupied,
-                    &mut candidates,
-                    self.last_passed,
-                );
-                self.board.put(self.side, position);
-                let (col, row) = BitBoard::col_row(position);
-                (Side::White, col, row)
+        }
+        let start = Instant::now();
+        let result = match side {
+            Side::Black => self.black_searcher.search(
+                &self.board,
+                side,
+                self.occupied,
+                &mut candidates,
+                self.last_passed,
+            ),
+            Side::White => self.white_searcher.search(
+                &self.board,
+                side,
+                self.occupied,
+                &mut candidates,
+                self.last_passed,
+            ),
+        };
+        let elapsed = start.elapsed();
+        if let Some(remaining) = self.clock_mut(side) {
+            if !remaining.spend(elapsed) {
+                self.time_loss = clock.map(|clock| (side, clock));
+                return ActionResult::TimeLoss(side);
             }
+        }
+        let position = result.position;
+        let flips = match self.board.legal_flips(side, position) {
+            Ok(flips) => flips,
+            Err(e) => panic!("{:?} searcher returned an illegal move: {}", side, e),
         };
-        self.side = self.side.flip();
-        self.last_passed = false;
-        self.occupied += 1;
-        ActionResult::Put(side, col, row)
+        self.record(Ply {
+            side,
+            mv: Move::Put(position),
+            flips,
+            elapsed,
+            clock,
+        })
+    }
+
+    /// Play `position` for the side to move, bypassing its searcher
+    ///
+    /// This is how moves chosen outside the game, e.g. by a human, are
+    /// entered. Clocks are not consulted. Passes are still played with
+    /// [`Game::play_one_turn`], which does not call the searcher when there
+    /// is no legal move.
+    ///
+    /// After a loss on time nothing is played and
+    /// [`ActionResult::TimeLoss`] is returned, as by [`Game::play_one_turn`].
+    pub fn play_move(&mut self, position: Position) -> Result<ActionResult, PutError> {
+        if let Some((side, _)) = self.time_loss {
+            return Ok(ActionResult::TimeLoss(side));
+        }
+        let flips = self.board.legal_flips(self.side, position)?;
+        Ok(self.record(Ply {
+            side: self.side,
+            mv: Move::Put(position),
+            flips,
+            elapsed: Duration::ZERO,
+            clock: self.clock(self.side).copied(),
+        }))
+    }
+
+    fn record(&mut self, ply: Ply) -> ActionResult {
+        self.redo.clear();
+        self.apply(ply);
+        ply.action()
     }
 
+    /// Play until the end and return the disc counts
+    ///
+    /// A game lost on time stops early; see [`Game::time_loss`].
     pub fn play_game(&mut self) -> (Count, Count) {
         loop {
-            if let ActionResult::GameSet(b, w) = self.play_one_turn() {
-                break (b, w);
+            match self.play_one_turn() {
+                ActionResult::GameSet(b, w) => break (b, w),
+                ActionResult::TimeLoss(_) => break self.board.count(),
+                _ => (),
             }
         }
     }
@@ -1,6 +1,13 @@
 mod board;
+mod clock;
 mod game;
+pub mod nboard;
+pub mod notation;
+mod record;
+pub mod render;
 pub mod search;
 
 pub use board::*;
+pub use clock::*;
 pub use game::*;
+pub use record::*;
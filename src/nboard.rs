@@ -0,0 +1,410 @@
+//! The NBoard text protocol, as an engine ([`Engine`]) and as a client of
+//! external engines ([`ExternalEngine`])
+//!
+//! The GUI sends one command per line (`nboard`, `set depth`, `set game`,
+//! `move`, `hint`, `go`, `ping`, `quit`) and the engine answers `set myname`,
+//! `search`, `===` and `pong` lines. Moves are written `F5`, passes `PA`, and
+//! evaluations are in discs for the side to move.
+
+mod client;
+
+pub use self::client::*;
+
+use std::{error::Error, fmt, io, sync::Arc, time::Instant};
+
+use crate::{
+    notation::{parse_square, replay, Move, NotationError},
+    search::{
+        AlphaBetaSearch, Evaluate, Evaluation, ExactSolver, Limit, MoveOrdering, Search,
+        SimpleEvaluator, DISC,
+    },
+    BitBoard, ParseSetupError, Setup, Side,
+};
+
+/// Board and moves of a game in Generic Game Format
+///
+/// Only the `BO` tag and the `B`/`W` moves are read; evaluations and times
+/// after `/` in a move are ignored.
+///
+/// ```rust
+/// # use reversi::{nboard::Ggf, BitBoard, Side};
+/// let ggf = "(;GM[Othello]PB[a]PW[b]TY[8]\
+///     BO[8 ---------------------------O*------*O--------------------------- *]\
+///     B[F5//1.2]W[d6];)";
+/// let game: Ggf = ggf.parse().unwrap();
+/// assert_eq!(game.setup.board, BitBoard::new());
+/// let (board, side) = game.position().unwrap();
+/// assert_eq!(board.count(), (3, 3));
+/// assert_eq!(side, Side::Black);
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Ggf {
+    pub setup: Setup,
+    pub moves: Vec<Move>,
+}
+
+impl Ggf {
+    pub const fn new(setup: Setup) -> Ggf {
+        Ggf {
+            setup,
+            moves: Vec::new(),
+        }
+    }
+
+    /// The position after all the moves
+    pub fn position(&self) -> Result<(BitBoard, Side), NotationError> {
+        replay(&self.setup.board, self.setup.side, &self.moves)
+    }
+}
+
+/// Moves are written for alternate sides, so passes must be explicit
+impl fmt::Display for Ggf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("(;GM[Othello]PC[reversi]TY[8]BO[8 ")?;
+        let board = &self.setup.board;
+        let mut mask = 0x8000000000000000;
+        while mask != 0 {
+            if board.black() & mask != 0 {
+                f.write_str("*")?;
+            } else if board.white() & mask != 0 {
+                f.write_str("O")?;
+            } else {
+                f.write_str("-")?;
+            }
+            mask >>= 1;
+        }
+        let mut side = self.setup.side;
+        f.write_str(match side {
+            Side::Black => " *]",
+            Side::White => " O]",
+        })?;
+        for &mv in self.moves.iter() {
+            let tag = match side {
+                Side::Black => "B",
+                Side::White => "W",
+            };
+            write!(f, "{}[{}]", tag, format_move(mv))?;
+            side = side.flip();
+        }
+        f.write_str(";)")
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum GgfError {
+    /// The game has no `BO[8 ...]` tag
+    MissingBoard,
+    InvalidBoard(ParseSetupError),
+    InvalidMove(String),
+    /// A `[` without its `]`
+    Unterminated,
+}
+
+impl fmt::Display for GgfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GgfError::MissingBoard => f.write_str("no 8x8 board in the game"),
+            GgfError::InvalidBoard(e) => write!(f, "invalid board: {}", e),
+            GgfError::InvalidMove(s) => write!(f, "invalid move {:?}", s),
+            GgfError::Unterminated => f.write_str("unterminated tag"),
+        }
+    }
+}
+
+impl Error for GgfError {}
+
+fn parse_move(s: &str) -> Option<Move> {
+    let s = s.split('/').next().unwrap_or_default().trim();
+    if s.eq_ignore_ascii_case("pa") || s.eq_ignore_ascii_case("pass") {
+        Some(Move::Pass)
+    } else {
+        parse_square(s).map(Move::Put)
+    }
+}
+
+impl std::str::FromStr for Ggf {
+    type Err = GgfError;
+
+    fn from_str(s: &str) -> Result<Ggf, GgfError> {
+        let mut setup = None;
+        let mut moves = Vec::new();
+        let mut rest = s;
+        while let Some(open) = rest.find('[') {
+            let tag = rest[..open]
+                .trim_end()
+                .rsplit(|c: char| !c.is_ascii_alphabetic())
+                .next()
+                .unwrap_or_default();
+            let close = rest[open..].find(']').ok_or(GgfError::Unterminated)? + open;
+            let value = &rest[open + 1..close];
+            match tag {
+                "BO" => {
+                    let board = value
+                        .trim()
+                        .strip_prefix('8')
+                        .ok_or(GgfError::MissingBoard)?;
+                    setup = Some(board.parse().map_err(GgfError::InvalidBoard)?);
+                }
+                "B" | "W" => {
+                    let mv = parse_move(value)
+                        .ok_or_else(|| GgfError::InvalidMove(value.to_string()))?;
+                    moves.push(mv);
+                }
+                _ => (),
+            }
+            rest = &rest[close + 1..];
+        }
+        Ok(Ggf {
+            setup: setup.ok_or(GgfError::MissingBoard)?,
+            moves,
+        })
+    }
+}
+
+/// Move in NBoard notation
+pub fn format_move(mv: Move) -> String {
+    mv.to_string().to_uppercase()
+}
+
+#[derive(Debug)]
+pub enum NboardError {
+    Io(io::Error),
+    UnknownCommand(String),
+    InvalidArgument(String),
+    Game(GgfError),
+    IllegalMove(NotationError),
+}
+
+impl fmt::Display for NboardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NboardError::Io(e) => write!(f, "{}", e),
+            NboardError::UnknownCommand(s) => write!(f, "unknown command {:?}", s),
+            NboardError::InvalidArgument(s) => write!(f, "invalid argument in {:?}", s),
+            NboardError::Game(e) => write!(f, "{}", e),
+            NboardError::IllegalMove(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for NboardError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NboardError::Io(e) => Some(e),
+            NboardError::Game(e) => Some(e),
+            NboardError::IllegalMove(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for NboardError {
+    fn from(e: io::Error) -> NboardError {
+        NboardError::Io(e)
+    }
+}
+
+/// Protocol state: the current position and the search settings
+///
+/// Positions with at most `exact_empties` empty squares are solved exactly,
+/// the others are searched with alpha-beta to the depth set by the GUI.
+pub struct Engine {
+    board: BitBoard,
+    side: Side,
+    depth: u8,
+    exact_empties: u8,
+    evaluator: Arc<dyn Evaluate + Send + Sync>,
+    solver: ExactSolver,
+}
+
+impl fmt::Debug for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Engine")
+            .field("board", &self.board)
+            .field("side", &self.side)
+            .field("depth", &self.depth)
+            .field("exact_empties", &self.exact_empties)
+            .finish()
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Engine {
+        Engine::new()
+    }
+}
+
+impl Engine {
+    pub fn new() -> Engine {
+        Engine {
+            board: BitBoard::new(),
+            side: Side::Black,
+            depth: 6,
+            exact_empties: 14,
+            evaluator: Arc::new(SimpleEvaluator),
+            solver: ExactSolver::new(1 << 20).with_ordering(MoveOrdering::ALL),
+        }
+    }
+
+    pub fn with_evaluator(mut self, evaluator: Arc<dyn Evaluate + Send + Sync>) -> Engine {
+        self.evaluator = evaluator;
+        self
+    }
+
+    pub fn with_exact_empties(mut self, empties: u8) -> Engine {
+        self.exact_empties = empties;
+        self
+    }
+
+    pub fn board(&self) -> &BitBoard {
+        &self.board
+    }
+
+    /// Side to move
+    pub fn side(&self) -> Side {
+        self.side
+    }
+
+    fn empties(&self) -> u8 {
+        let (b, w) = self.board.count();
+        64 - b - w
+    }
+
+    fn play(&mut self, mv: Move) -> Result<(), NboardError> {
+        let (board, side) =
+            replay(&self.board, self.side, &[mv]).map_err(NboardError::IllegalMove)?;
+        self.board = board;
+        self.side = side;
+        Ok(())
+    }
+
+    /// Best move and its evaluation in discs
+    fn best(&mut self) -> (Move, f64) {
+        let mut candidates = self.board.candidates(self.side);
+        if candidates.len() == 0 {
+            return (Move::Pass, 0.0);
+        }
+        if self.empties() <= self.exact_empties {
+            let (position, score) =
+                self.solver
+                    .solve_with_candidates(&self.board, self.side, &mut candidates);
+            (Move::Put(position), score.mine as f64 - score.opp as f64)
+        } else {
+            let mut search = AlphaBetaSearch::new(self.evaluator.clone(), Limit::Depth(self.depth));
+            let result = search.search(
+                &self.board,
+                self.side,
+                64 - self.empties(),
+                &mut candidates,
+                false,
+            );
+            let score = match result.score {
+                Some(Evaluation::Estimate(e)) => e.0 as f64 / DISC as f64,
+                _ => 0.0,
+            };
+            (Move::Put(result.position), score)
+        }
+    }
+
+    /// Up to `n` moves with their evaluations in discs and the search depth,
+    /// best first
+    fn hints(&mut self, n: usize) -> Vec<(Move, f64, String)> {
+        if self.board.candidates(self.side).len() == 0 {
+            return vec![(Move::Pass, 0.0, self.depth.to_string())];
+        }
+        let empties = self.empties();
+        let mut hints = if empties <= self.exact_empties {
+            self.solver
+                .analyze(&self.board, self.side)
+                .into_iter()
+                .map(|(p, s)| {
+                    (
+                        Move::Put(p),
+                        s.mine as f64 - s.opp as f64,
+                        format!("{}@100%", empties),
+                    )
+                })
+                .collect::<Vec<_>>()
+        } else {
+            let mut search = AlphaBetaSearch::new(self.evaluator.clone(), Limit::Depth(self.depth));
+            search
+                .analyze(&self.board, self.side, 64 - empties)
+                .into_iter()
+                .map(|(p, e)| {
+                    (
+                        Move::Put(p),
+                        e.0 as f64 / DISC as f64,
+                        self.depth.min(empties).to_string(),
+                    )
+                })
+                .collect()
+        };
+        hints.truncate(n);
+        hints
+    }
+
+    /// Handle one command line, writing the answers to `out`
+    ///
+    /// Returns `Ok(false)` after `quit`. Errors leave the state unchanged.
+    pub fn handle<W: io::Write>(&mut self, line: &str, out: &mut W) -> Result<bool, NboardError> {
+        let line = line.trim();
+        let (command, args) = match line.find(char::is_whitespace) {
+            Some(i) => (&line[..i], line[i..].trim_start()),
+            None => (line, ""),
+        };
+        let invalid = || NboardError::InvalidArgument(line.to_string());
+        match command {
+            "" => (),
+            "nboard" => writeln!(out, "set myname reversi")?,
+            "quit" => return Ok(false),
+            "ping" => writeln!(out, "pong {}", args)?,
+            "set" => {
+                let (key, value) = match args.find(char::is_whitespace) {
+                    Some(i) => (&args[..i], args[i..].trim_start()),
+                    None => (args, ""),
+                };
+                match key {
+                    "depth" => match value.parse() {
+                        Ok(depth) if (1..=60).contains(&depth) => self.depth = depth,
+                        _ => return Err(invalid()),
+                    },
+                    "game" => {
+                        let game: Ggf = value.parse().map_err(NboardError::Game)?;
+                        let (board, side) = game.position().map_err(NboardError::IllegalMove)?;
+                        self.board = board;
+                        self.side = side;
+                    }
+                    // 引き分けの扱いは変えない
+                    "contempt" => (),
+                    _ => return Err(NboardError::UnknownCommand(line.to_string())),
+                }
+            }
+            "move" => {
+                let mv = parse_move(args).ok_or_else(invalid)?;
+                self.play(mv)?;
+            }
+            "go" => {
+                let start = Instant::now();
+                let (mv, eval) = self.best();
+                writeln!(
+                    out,
+                    "=== {}/{:.2}/{:.3}",
+                    format_move(mv),
+                    eval,
+                    start.elapsed().as_secs_f64()
+                )?;
+            }
+            "hint" => {
+                let n = args.parse().map_err(|_| invalid())?;
+                for (mv, eval, depth) in self.hints(n) {
+                    writeln!(out, "search {} {:.2} 0 {}", format_move(mv), eval, depth)?;
+                }
+                writeln!(out, "status")?;
+            }
+            // 学習や解析モードは未対応
+            "learn" | "analyze" => (),
+            _ => return Err(NboardError::UnknownCommand(line.to_string())),
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,255 @@
+use std::{
+    error::Error,
+    fmt,
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{format_move, parse_move, Ggf};
+use crate::{
+    notation::Move,
+    search::{Estimate, Evaluation, Occupied, Search, SearchResult, DISC},
+    BitBoard, Candidate, Setup, Side,
+};
+
+#[derive(Debug)]
+pub enum EngineError {
+    Spawn(io::Error),
+    /// Writing a command failed
+    Io(io::Error),
+    /// The engine closed its input or output
+    Exited,
+    /// No answer to `command` within the timeout
+    Timeout {
+        command: String,
+    },
+    /// An answer which can not be parsed
+    InvalidResponse(String),
+    /// The engine chose a move which is not legal in the position
+    IllegalMove(String),
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EngineError::Spawn(e) => write!(f, "failed to start the engine: {}", e),
+            EngineError::Io(e) => write!(f, "failed to talk to the engine: {}", e),
+            EngineError::Exited => f.write_str("the engine exited"),
+            EngineError::Timeout { command } => {
+                write!(f, "the engine did not answer {:?} in time", command)
+            }
+            EngineError::InvalidResponse(s) => write!(f, "invalid response {:?}", s),
+            EngineError::IllegalMove(s) => write!(f, "illegal move {:?}", s),
+        }
+    }
+}
+
+impl Error for EngineError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EngineError::Spawn(e) | EngineError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// A subprocess speaking the NBoard protocol, used as a searcher
+///
+/// Every search sends the position as a GGF game without moves followed by
+/// `go`, so the engine keeps no state between searches.
+///
+/// The NBoard protocol has no command for the time of a move, so the engine
+/// is not told about the limit of [`Search::set_time_limit`]; bound its
+/// search with [`ExternalEngine::set_depth`] to keep it within a clock.
+#[derive(Debug)]
+pub struct ExternalEngine {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    name: Option<String>,
+    timeout: Duration,
+    ping: u64,
+}
+
+impl ExternalEngine {
+    /// Start `command` and wait for it to answer a `ping` within `timeout`
+    ///
+    /// `timeout` also bounds every later search.
+    pub fn spawn(command: &mut Command, timeout: Duration) -> Result<ExternalEngine, EngineError> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(EngineError::Spawn)?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, lines) = mpsc::channel();
+        // 読み込みは専用スレッドで行い、タイムアウト付きで受け取る
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => {
+                        if sender.send(line).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }
+        });
+        let mut engine = ExternalEngine {
+            child,
+            stdin,
+            lines,
+            name: None,
+            timeout,
+            ping: 0,
+        };
+        engine.send("nboard 2")?;
+        engine.sync()?;
+        Ok(engine)
+    }
+
+    /// Name announced with `set myname`, if any
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Ask the engine to search `depth` plies
+    pub fn set_depth(&mut self, depth: u8) -> Result<(), EngineError> {
+        self.send(&format!("set depth {}", depth))?;
+        self.sync()
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), EngineError> {
+        writeln!(self.stdin, "{}", command)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::BrokenPipe => EngineError::Exited,
+                _ => EngineError::Io(e),
+            })
+    }
+
+    /// Wait for the first line accepted by `f`, skipping the others
+    fn wait_for<T>(
+        &mut self,
+        command: &str,
+        mut f: impl FnMut(&str) -> Option<T>,
+    ) -> Result<T, EngineError> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let line = match self.lines.recv_timeout(left) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(EngineError::Timeout {
+                        command: command.to_string(),
+                    })
+                }
+                Err(RecvTimeoutError::Disconnected) => return Err(EngineError::Exited),
+            };
+            let line = line.trim();
+            if let Some(name) = line.strip_prefix("set myname ") {
+                self.name = Some(name.trim().to_string());
+            }
+            if let Some(value) = f(line) {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Ping the engine and wait for the matching pong
+    fn sync(&mut self) -> Result<(), EngineError> {
+        self.ping += 1;
+        let command = format!("ping {}", self.ping);
+        self.send(&command)?;
+        let pong = format!("pong {}", self.ping);
+        self.wait_for(&command, |line| (line == pong).then_some(()))
+    }
+
+    /// Let the engine choose a move for `side`, checking that it is legal
+    pub fn try_search(
+        &mut self,
+        board: &BitBoard,
+        side: Side,
+    ) -> Result<SearchResult, EngineError> {
+        // 前の探索の残りを読み捨てる
+        self.sync()?;
+        let game = Ggf::new(Setup::new(board.clone(), side));
+        self.send(&format!("set game {}", game))?;
+        self.send("go")?;
+        let answer = self.wait_for("go", |line| {
+            line.strip_prefix("===").map(|s| s.trim().to_string())
+        })?;
+        let mut fields = answer.split('/');
+        let mv = fields.next().unwrap_or_default();
+        let position = match parse_move(mv) {
+            Some(Move::Put(position)) => position,
+            Some(Move::Pass) => return Err(EngineError::IllegalMove(format_move(Move::Pass))),
+            None => return Err(EngineError::InvalidResponse(answer)),
+        };
+        if !board.candidates(side).contains(position) {
+            return Err(EngineError::IllegalMove(mv.to_string()));
+        }
+        let score = fields
+            .next()
+            .and_then(|e| e.trim().parse::<f64>().ok())
+            .map(|e| Evaluation::Estimate(Estimate((e * DISC as f64).round() as i32)));
+        Ok(SearchResult {
+            score,
+            ..SearchResult::new(position)
+        })
+    }
+}
+
+impl Search for ExternalEngine {
+    /// # Panics
+    ///
+    /// Panics if the engine fails or chooses a move outside `candidates`;
+    /// use [`ExternalEngine::try_search`] to handle the error instead.
+    fn search(
+        &mut self,
+        board: &BitBoard,
+        side: Side,
+        _occupied: Occupied,
+        candidates: &mut Candidate,
+        _last_passed: bool,
+    ) -> SearchResult {
+        // エンジンには候補手を伝えられないので、結果を確かめる
+        let result = self.try_search(board, side).and_then(|result| {
+            if candidates.contains(result.position) {
+                Ok(result)
+            } else {
+                let mv = format_move(Move::Put(result.position));
+                Err(EngineError::IllegalMove(mv))
+            }
+        });
+        match result {
+            Ok(result) => result,
+            Err(e) => panic!("external engine failed: {}", e),
+        }
+    }
+}
+
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        // quit に従わないエンジンは止める
+        let deadline = Instant::now() + Duration::from_millis(500);
+        while Instant::now() < deadline {
+            match self.child.try_wait() {
+                Ok(Some(_)) | Err(_) => return,
+                Ok(None) => thread::sleep(Duration::from_millis(10)),
+            }
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
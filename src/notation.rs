@@ -0,0 +1,186 @@
+//! Square names (`a1`..`h8`) and move transcripts such as `f5d6c3d3c4`.
+//!
+//! Columns are `a`..`h` from left to right and rows are `1`..`8` from top to
+//! bottom, so the initial position has black discs on `d5` and `e4`.
+
+use std::{error::Error, fmt, time::Duration};
+
+use crate::{BitBoard, Ply, Position, Side};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Move {
+    Put(Position),
+    Pass,
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Move::Put(position) => f.write_str(&square_name(position)),
+            Move::Pass => f.write_str("pa"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum NotationError {
+    /// The `index`-th token is neither a square name nor a pass
+    InvalidToken { index: usize, token: String },
+    /// The `index`-th move can not be played by `side`
+    IllegalMove { index: usize, side: Side, mv: Move },
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NotationError::InvalidToken { index, token } => {
+                write!(f, "invalid token {:?} at move {}", token, index + 1)
+            }
+            NotationError::IllegalMove { index, side, mv } => {
+                write!(f, "illegal move {} by {:?} at move {}", mv, side, index + 1)
+            }
+        }
+    }
+}
+
+impl Error for NotationError {}
+
+/// Name of a single square
+///
+/// ```rust
+/// # use reversi::{BitBoard, notation::square_name};
+/// assert_eq!(square_name(BitBoard::position(0, 0)), "a1");
+/// assert_eq!(square_name(BitBoard::position(5, 4)), "f5");
+/// ```
+pub fn square_name(position: Position) -> String {
+    debug_assert_eq!(position.count_ones(), 1);
+    let (col, row) = BitBoard::col_row(position);
+    let mut name = String::with_capacity(2);
+    name.push((b'a' + col as u8) as char);
+    name.push((b'1' + row as u8) as char);
+    name
+}
+
+fn parse_token(token: &[u8]) -> Option<Move> {
+    match token {
+        b"pa" | b"PA" | b"--" => Some(Move::Pass),
+        &[c, r] => {
+            let col = c.to_ascii_lowercase().wrapping_sub(b'a') as usize;
+            let row = r.wrapping_sub(b'1') as usize;
+            if col < 8 && row < 8 {
+                Some(Move::Put(BitBoard::position(col, row)))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Parse a single square name, case insensitive
+///
+/// ```rust
+/// # use reversi::{BitBoard, notation::parse_square};
+/// assert_eq!(parse_square("F5"), Some(BitBoard::position(5, 4)));
+/// assert_eq!(parse_square("i9"), None);
+/// ```
+pub fn parse_square(s: &str) -> Option<Position> {
+    match parse_token(s.as_bytes()) {
+        Some(Move::Put(position)) => Some(position),
+        _ => None,
+    }
+}
+
+/// Parse a transcript into moves
+///
+/// Whitespace is ignored, and `pa` (or `--`) stands for an explicit pass.
+pub fn parse_transcript(s: &str) -> Result<Vec<Move>, NotationError> {
+    let bytes = s
+        .bytes()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect::<Vec<_>>();
+    bytes
+        .chunks(2)
+        .enumerate()
+        .map(|(index, token)| {
+            parse_token(token).ok_or_else(|| NotationError::InvalidToken {
+                index,
+                token: String::from_utf8_lossy(token).into_owned(),
+            })
+        })
+        .collect()
+}
+
+pub fn format_transcript(moves: &[Move]) -> String {
+    let mut s = String::with_capacity(moves.len() * 2);
+    for mv in moves {
+        s.push_str(&mv.to_string());
+    }
+    s
+}
+
+/// Replay `moves` starting from `board` with `side` to move
+///
+/// Passes may be omitted from the transcript; they are inserted whenever the
+/// side to move has no candidate. Returns the resulting board and the side to
+/// move next.
+pub fn replay(
+    board: &BitBoard,
+    side: Side,
+    moves: &[Move],
+) -> Result<(BitBoard, Side), NotationError> {
+    replay_plies(board, side, moves).map(|(board, side, _)| (board, side))
+}
+
+/// [`replay`], also returning the plies played, inserted passes included
+///
+/// The plies have no time or clock.
+pub fn replay_plies(
+    board: &BitBoard,
+    mut side: Side,
+    moves: &[Move],
+) -> Result<(BitBoard, Side, Vec<Ply>), NotationError> {
+    let mut board = board.clone();
+    let mut plies = Vec::with_capacity(moves.len());
+    let ply = |side, mv, flips| Ply {
+        side,
+        mv,
+        flips,
+        elapsed: Duration::ZERO,
+        clock: None,
+    };
+    for (index, &mv) in moves.iter().enumerate() {
+        let mut candidates = board.candidates(side);
+        if let Move::Put(_) = mv {
+            if candidates.len() == 0 {
+                plies.push(ply(side, Move::Pass, 0));
+                side = side.flip();
+                candidates = board.candidates(side);
+            }
+        }
+        let flips = match mv {
+            Move::Put(position) if candidates.contains(position) => {
+                let flips = board.flips(side, position);
+                board.make(side, position, flips);
+                flips
+            }
+            Move::Pass if candidates.len() == 0 => 0,
+            _ => return Err(NotationError::IllegalMove { index, side, mv }),
+        };
+        plies.push(ply(side, mv, flips));
+        side = side.flip();
+    }
+    Ok((board, side, plies))
+}
+
+/// Replay a transcript from the initial position
+///
+/// ```rust
+/// # use reversi::{notation::replay_transcript, Side};
+/// let (board, side) = replay_transcript("f5d6c3d3c4").unwrap();
+/// assert_eq!(board.count(), (6, 3));
+/// assert_eq!(side, Side::White);
+/// ```
+pub fn replay_transcript(s: &str) -> Result<(BitBoard, Side), NotationError> {
+    replay(&BitBoard::new(), Side::Black, &parse_transcript(s)?)
+}
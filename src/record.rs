@@ -0,0 +1,63 @@
+use crate::search::Wld;
+
+/// Results of one player, against one opponent or a whole field
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Record {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Record {
+    pub const fn new(wins: u32, draws: u32, losses: u32) -> Record {
+        Record {
+            wins,
+            draws,
+            losses,
+        }
+    }
+
+    pub fn add(&mut self, wld: Wld) {
+        match wld {
+            Wld::Win => self.wins += 1,
+            Wld::Draw => self.draws += 1,
+            Wld::Loss => self.losses += 1,
+        }
+    }
+
+    pub fn merge(&mut self, other: Record) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+
+    pub const fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Points per game, a draw counting half; NaN without games
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64
+    }
+
+    /// Elo difference and the bounds of its 95% confidence interval
+    pub fn elo(&self) -> (f64, f64, f64) {
+        let n = self.games() as f64;
+        let p = self.score();
+        let variance = (self.wins as f64 * (1.0 - p).powi(2)
+            + self.draws as f64 * (0.5 - p).powi(2)
+            + self.losses as f64 * p.powi(2))
+            / n;
+        let margin = 1.96 * (variance / n).sqrt();
+        (elo(p), elo(p - margin), elo(p + margin))
+    }
+}
+
+/// Elo difference expected to give `score` points per game
+///
+/// The score is clamped to `[0.001, 0.999]`, so a perfect score gives about
+/// ±1200 rather than infinity.
+pub fn elo(score: f64) -> f64 {
+    let score = score.clamp(1e-3, 1.0 - 1e-3);
+    -400.0 * (1.0 / score - 1.0).log10()
+}
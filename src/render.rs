@@ -0,0 +1,191 @@
+//! Rendering of boards as text for terminals and debug output, and as SVG
+
+use std::fmt;
+
+use crate::{
+    board::{BitBoard, Position, Side},
+    game::Ply,
+    notation::Move,
+};
+
+mod svg;
+pub use self::svg::*;
+
+const RESET: &str = "\x1b[0m";
+const BOARD: u8 = 42;
+const LAST_MOVE: u8 = 43;
+const FLIPPED: u8 = 46;
+
+/// A board rendered as text, created with [`Render::new`] and shown with
+/// `Display`
+///
+/// By default the grid has column and row labels; empty squares are `_`,
+/// black and white discs `●` and `○` and the marked candidates `*`, as in
+/// [`BitBoard::format`]. Without colour the last move is put in brackets,
+/// or drawn as `◆` or `◇` in the compact form so that every row stays 8
+/// characters wide; with colour it and the discs it flipped get their own
+/// background.
+///
+/// ```rust
+/// # use reversi::{BitBoard, Side, render::Render};
+/// let board = BitBoard::new();
+/// let render = Render::new(&board).with_candidates(Side::Black);
+/// assert!(render.to_string().starts_with("  a b c d e f g h\n1 _ _ _"));
+/// assert_eq!(
+///     render.with_compact(true).to_string(),
+///     "________/________/___*____/__*○●___/___●○*__/____*___/________/________"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Render<'a> {
+    board: &'a BitBoard,
+    candidates: u64,
+    last_move: Position,
+    flips: u64,
+    labels: bool,
+    colour: bool,
+    compact: bool,
+}
+
+impl<'a> Render<'a> {
+    pub const fn new(board: &'a BitBoard) -> Render<'a> {
+        Render {
+            board,
+            candidates: 0,
+            last_move: 0,
+            flips: 0,
+            labels: true,
+            colour: false,
+            compact: false,
+        }
+    }
+
+    /// Mark the legal moves of `side`
+    pub fn with_candidates(mut self, side: Side) -> Render<'a> {
+        self.candidates = self.board.candidates(side).fold(0, |acc, p| acc | p);
+        self
+    }
+
+    /// Highlight the move of `ply` and the discs it flipped; passes mark
+    /// nothing
+    pub fn with_last_move(mut self, ply: &Ply) -> Render<'a> {
+        (self.last_move, self.flips) = match ply.mv {
+            Move::Put(position) => (position, ply.flips),
+            Move::Pass => (0, 0),
+        };
+        self
+    }
+
+    pub const fn with_labels(mut self, labels: bool) -> Render<'a> {
+        self.labels = labels;
+        self
+    }
+
+    /// Use ANSI escape sequences
+    pub const fn with_colour(mut self, colour: bool) -> Render<'a> {
+        self.colour = colour;
+        self
+    }
+
+    /// Put the rows on a single line separated by `/`, without labels
+    pub const fn with_compact(mut self, compact: bool) -> Render<'a> {
+        self.compact = compact;
+        self
+    }
+
+    /// Delimiter in front of `col`, which brackets the last move when there
+    /// is neither colour nor a glyph to show it
+    fn separator(&self, col: usize, row: usize) -> char {
+        if self.colour || self.compact || self.last_move == 0 {
+            ' '
+        } else if col < 8 && self.last_move == BitBoard::position(col, row) {
+            '['
+        } else if col > 0 && self.last_move == BitBoard::position(col - 1, row) {
+            ']'
+        } else {
+            ' '
+        }
+    }
+
+    fn write_separator(&self, f: &mut fmt::Formatter, separator: char) -> fmt::Result {
+        if self.colour {
+            write!(f, "\x1b[{}m{}", BOARD, separator)
+        } else {
+            write!(f, "{}", separator)
+        }
+    }
+
+    fn write_square(&self, f: &mut fmt::Formatter, position: Position) -> fmt::Result {
+        // 1 行 8 文字の形式では括弧の代わりに記号を変える
+        let marked = self.compact && !self.colour && position == self.last_move;
+        let (symbol, fg) = if self.board.black() & position != 0 {
+            (if marked { '◆' } else { '●' }, 30)
+        } else if self.board.white() & position != 0 {
+            (
+                match (self.colour, marked) {
+                    (true, _) => '●',
+                    (false, true) => '◇',
+                    (false, false) => '○',
+                },
+                97,
+            )
+        } else if self.candidates & position != 0 {
+            ('*', 93)
+        } else {
+            ('_', 32)
+        };
+        if !self.colour {
+            return write!(f, "{}", symbol);
+        }
+        let bg = if position == self.last_move {
+            LAST_MOVE
+        } else if self.flips & position != 0 {
+            FLIPPED
+        } else {
+            BOARD
+        };
+        write!(f, "\x1b[{};{}m{}", bg, fg, symbol)
+    }
+
+    fn write_row(&self, f: &mut fmt::Formatter, row: usize, spaced: bool) -> fmt::Result {
+        for col in 0..=8 {
+            let separator = self.separator(col, row);
+            // 行頭と行末の空白は省く
+            if separator != ' ' || (spaced && col < 8 && (col > 0 || self.labels)) {
+                self.write_separator(f, separator)?;
+            }
+            if col < 8 {
+                self.write_square(f, BitBoard::position(col, row))?;
+            }
+        }
+        if self.colour {
+            f.write_str(RESET)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Render<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.compact {
+            for row in 0..8 {
+                if row > 0 {
+                    f.write_str("/")?;
+                }
+                self.write_row(f, row, false)?;
+            }
+            return Ok(());
+        }
+        if self.labels {
+            writeln!(f, "  a b c d e f g h")?;
+        }
+        for row in 0..8 {
+            if self.labels {
+                write!(f, "{}", row + 1)?;
+            }
+            self.write_row(f, row, true)?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,416 @@
+use std::{fmt, time::Duration};
+
+use crate::{
+    board::{BitBoard, Position, Side},
+    game::Ply,
+    notation::Move,
+};
+
+const BOARD_FILL: &str = "#2e7d32";
+const LAST_MOVE_FILL: &str = "#e53935";
+
+/// Layout shared by [`Svg`] and [`SvgAnimation`]
+#[derive(Debug, Clone)]
+struct Style {
+    square: f64,
+    coordinates: bool,
+}
+
+impl Style {
+    fn margin(&self) -> f64 {
+        if self.coordinates {
+            self.square * 0.6
+        } else {
+            0.0
+        }
+    }
+
+    fn size(&self) -> f64 {
+        self.square * 8.0 + self.margin() * 2.0
+    }
+
+    fn center(&self, position: Position) -> (f64, f64) {
+        let (col, row) = BitBoard::col_row(position);
+        let m = self.margin();
+        (
+            m + (col as f64 + 0.5) * self.square,
+            m + (row as f64 + 0.5) * self.square,
+        )
+    }
+
+    fn write_header<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        let size = self.size();
+        writeln!(
+            w,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{0}" viewBox="0 0 {0} {0}">"#,
+            size
+        )?;
+        let m = self.margin();
+        let s = self.square;
+        writeln!(
+            w,
+            r#"<rect x="{0}" y="{0}" width="{1}" height="{1}" fill="{2}"/>"#,
+            m,
+            s * 8.0,
+            BOARD_FILL
+        )?;
+        for i in 0..=8 {
+            let x = m + i as f64 * s;
+            writeln!(
+                w,
+                r#"<line x1="{0}" y1="{1}" x2="{0}" y2="{2}" stroke="black"/>"#,
+                x,
+                m,
+                m + s * 8.0
+            )?;
+            writeln!(
+                w,
+                r#"<line x1="{1}" y1="{0}" x2="{2}" y2="{0}" stroke="black"/>"#,
+                x,
+                m,
+                m + s * 8.0
+            )?;
+        }
+        // 星の位置 (c3, g3, c7, g7 の角)
+        for &(x, y) in [(2.0, 2.0), (6.0, 2.0), (2.0, 6.0), (6.0, 6.0)].iter() {
+            writeln!(
+                w,
+                r#"<circle cx="{}" cy="{}" r="{}"/>"#,
+                m + x * s,
+                m + y * s,
+                s * 0.07
+            )?;
+        }
+        if self.coordinates {
+            let font = s * 0.35;
+            for i in 0..8 {
+                let c = m + (i as f64 + 0.5) * s;
+                writeln!(
+                    w,
+                    r#"<text x="{}" y="{}" font-size="{}" text-anchor="middle" font-family="sans-serif">{}</text>"#,
+                    c,
+                    m * 0.7,
+                    font,
+                    (b'a' + i) as char
+                )?;
+                writeln!(
+                    w,
+                    r#"<text x="{}" y="{}" font-size="{}" text-anchor="middle" dominant-baseline="central" font-family="sans-serif">{}</text>"#,
+                    m * 0.5,
+                    c,
+                    font,
+                    i + 1
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_discs<W: fmt::Write>(&self, w: &mut W, board: &BitBoard) -> fmt::Result {
+        let r = self.square * 0.42;
+        for (mask, fill) in [(board.black(), "black"), (board.white(), "white")].iter() {
+            let mut rest = *mask;
+            while rest != 0 {
+                let position = 0x8000000000000000 >> rest.leading_zeros();
+                rest &= !position;
+                let (cx, cy) = self.center(position);
+                writeln!(
+                    w,
+                    r#"<circle cx="{}" cy="{}" r="{}" fill="{}" stroke="black"/>"#,
+                    cx, cy, r, fill
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Text centred on `position`, readable on whatever is drawn there
+    fn write_label<W: fmt::Write>(
+        &self,
+        w: &mut W,
+        board: &BitBoard,
+        position: Position,
+        text: &str,
+    ) -> fmt::Result {
+        let (cx, cy) = self.center(position);
+        let fill = if board.black() & position != 0 {
+            "white"
+        } else {
+            "black"
+        };
+        writeln!(
+            w,
+            r#"<text x="{}" y="{}" font-size="{}" fill="{}" text-anchor="middle" dominant-baseline="central" font-family="sans-serif">{}</text>"#,
+            cx,
+            cy,
+            self.square * 0.38,
+            fill,
+            Escaped(text)
+        )
+    }
+
+    fn write_last_move<W: fmt::Write>(&self, w: &mut W, position: Position) -> fmt::Result {
+        let (cx, cy) = self.center(position);
+        writeln!(
+            w,
+            r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+            cx,
+            cy,
+            self.square * 0.1,
+            LAST_MOVE_FILL
+        )
+    }
+}
+
+/// Text with the XML special characters escaped
+struct Escaped<'a>(&'a str);
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '&' => f.write_str("&amp;")?,
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '"' => f.write_str("&quot;")?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A board drawn as an SVG image, written with `Display`
+///
+/// Move numbers are drawn on the discs, candidates as small dots of the
+/// colour of the side to move, and annotations (e.g. evaluations) as text
+/// on their squares.
+///
+/// ```rust
+/// # use reversi::{BitBoard, Side, render::Svg};
+/// let board = BitBoard::new();
+/// let svg = Svg::new(&board).with_candidates(Side::Black).to_string();
+/// assert!(svg.starts_with("<svg "));
+/// assert!(svg.trim_end().ends_with("</svg>"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Svg<'a> {
+    board: &'a BitBoard,
+    style: Style,
+    candidates: Option<Side>,
+    last_move: Position,
+    numbers: Vec<(Position, usize)>,
+    annotations: Vec<(Position, String)>,
+}
+
+impl<'a> Svg<'a> {
+    pub fn new(board: &'a BitBoard) -> Svg<'a> {
+        Svg {
+            board,
+            style: Style {
+                square: 40.0,
+                coordinates: true,
+            },
+            candidates: None,
+            last_move: 0,
+            numbers: Vec::new(),
+            annotations: Vec::new(),
+        }
+    }
+
+    /// Width of a square in pixels, 40 by default
+    pub fn with_square_size(mut self, square: f64) -> Svg<'a> {
+        self.style.square = square;
+        self
+    }
+
+    pub fn with_coordinates(mut self, coordinates: bool) -> Svg<'a> {
+        self.style.coordinates = coordinates;
+        self
+    }
+
+    /// Mark the legal moves of `side`
+    pub fn with_candidates(mut self, side: Side) -> Svg<'a> {
+        self.candidates = Some(side);
+        self
+    }
+
+    /// Mark the square of the last move with a dot
+    pub fn with_last_move(mut self, ply: &Ply) -> Svg<'a> {
+        self.last_move = match ply.mv {
+            Move::Put(position) => position,
+            Move::Pass => 0,
+        };
+        self
+    }
+
+    /// Number the discs in the order `history` placed them, passes not
+    /// counted
+    pub fn with_move_numbers(mut self, history: &[Ply]) -> Svg<'a> {
+        self.numbers = history
+            .iter()
+            .filter_map(|p| match p.mv {
+                Move::Put(position) => Some(position),
+                Move::Pass => None,
+            })
+            .enumerate()
+            .map(|(i, position)| (position, i + 1))
+            .collect();
+        self
+    }
+
+    /// Write `text` on `position`, over anything else drawn there
+    pub fn with_annotation(mut self, position: Position, text: impl Into<String>) -> Svg<'a> {
+        self.annotations.push((position, text.into()));
+        self
+    }
+}
+
+impl fmt::Display for Svg<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let style = &self.style;
+        style.write_header(f)?;
+        style.write_discs(f, self.board)?;
+        if let Some(side) = self.candidates {
+            let fill = match side {
+                Side::Black => "black",
+                Side::White => "white",
+            };
+            for position in self.board.candidates(side) {
+                let (cx, cy) = style.center(position);
+                writeln!(
+                    f,
+                    r#"<circle cx="{}" cy="{}" r="{}" fill="{}" fill-opacity="0.4"/>"#,
+                    cx,
+                    cy,
+                    style.square * 0.12,
+                    fill
+                )?;
+            }
+        }
+        let numbered = self.numbers.iter().fold(0, |acc, &(p, _)| acc | p);
+        if self.last_move & numbered == 0 && self.last_move != 0 {
+            style.write_last_move(f, self.last_move)?;
+        }
+        for (position, number) in self.numbers.iter() {
+            style.write_label(f, self.board, *position, &number.to_string())?;
+        }
+        for (position, text) in self.annotations.iter() {
+            style.write_label(f, self.board, *position, text)?;
+        }
+        writeln!(f, "</svg>")
+    }
+}
+
+/// A whole game as an animated SVG, one frame per ply after the starting
+/// position, looping forever
+///
+/// The frames are shown in turn with SMIL `<animate>`, which browsers play
+/// without scripts.
+#[derive(Debug, Clone)]
+pub struct SvgAnimation<'a> {
+    start: BitBoard,
+    history: &'a [Ply],
+    style: Style,
+    frame: Duration,
+}
+
+impl<'a> SvgAnimation<'a> {
+    /// Animate `history`, which led to `board`, as kept by a `Game`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `history` could not have led to `board`.
+    pub fn new(board: &BitBoard, history: &'a [Ply]) -> SvgAnimation<'a> {
+        let mut start = board.clone();
+        for ply in history.iter().rev() {
+            if let Move::Put(position) = ply.mv {
+                let mine = match ply.side {
+                    Side::Black => start.black(),
+                    Side::White => start.white(),
+                };
+                let placed = position | ply.flips;
+                assert_eq!(mine & placed, placed, "history does not lead to the board");
+                start.unmake(ply.side, position, ply.flips);
+            }
+        }
+        SvgAnimation {
+            start,
+            history,
+            style: Style {
+                square: 40.0,
+                coordinates: true,
+            },
+            frame: Duration::from_secs(1),
+        }
+    }
+
+    /// Time each frame is shown, one second by default
+    pub fn with_frame_duration(mut self, frame: Duration) -> SvgAnimation<'a> {
+        self.frame = frame;
+        self
+    }
+
+    pub fn with_square_size(mut self, square: f64) -> SvgAnimation<'a> {
+        self.style.square = square;
+        self
+    }
+
+    pub fn with_coordinates(mut self, coordinates: bool) -> SvgAnimation<'a> {
+        self.style.coordinates = coordinates;
+        self
+    }
+
+    /// Number of frames, the starting position included
+    pub fn frames(&self) -> usize {
+        self.history.len() + 1
+    }
+}
+
+impl fmt::Display for SvgAnimation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let style = &self.style;
+        let n = self.frames();
+        let total = self.frame.as_secs_f64() * n as f64;
+        style.write_header(f)?;
+        let mut board = self.start.clone();
+        for i in 0..n {
+            if i > 0 {
+                let ply = &self.history[i - 1];
+                if let Move::Put(position) = ply.mv {
+                    board.make(ply.side, position, ply.flips);
+                }
+            }
+            // 各フレームは自分の区間だけ表示する
+            let show = i as f64 / n as f64;
+            let hide = (i + 1) as f64 / n as f64;
+            let (initial, values, key_times) = if i == 0 {
+                (
+                    "visible",
+                    "visible;hidden".to_string(),
+                    format!("0;{}", hide),
+                )
+            } else {
+                (
+                    "hidden",
+                    "hidden;visible;hidden".to_string(),
+                    format!("0;{};{}", show, hide),
+                )
+            };
+            writeln!(f, r#"<g visibility="{}">"#, initial)?;
+            writeln!(
+                f,
+                r#"<animate attributeName="visibility" values="{}" keyTimes="{}" dur="{}s" calcMode="discrete" repeatCount="indefinite"/>"#,
+                values, key_times, total
+            )?;
+            style.write_discs(f, &board)?;
+            if i > 0 {
+                if let Move::Put(position) = self.history[i - 1].mv {
+                    style.write_last_move(f, position)?;
+                }
+            }
+            writeln!(f, "</g>")?;
+        }
+        writeln!(f, "</svg>")
+    }
+}
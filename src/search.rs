@@ -1,18 +1,97 @@
+mod alphabeta;
+mod exact;
+mod mcts;
+mod parallel;
+mod pattern;
 mod random;
-
+mod spec;
+mod stop;
+mod train;
+
+pub use self::alphabeta::*;
+pub use self::exact::*;
+pub use self::mcts::*;
+pub use self::parallel::*;
+pub use self::pattern::*;
 pub use self::random::*;
-use crate::{BitBoard, Candidate, Count, Position, Side, SIZE};
+pub use self::spec::*;
+pub use self::stop::StopCondition;
+use self::stop::Stopper;
+pub use self::train::*;
+use std::time::Duration;
+
+use crate::{notation::Move, BitBoard, Candidate, Count, Position, Side, SIZE};
 
 pub type Occupied = u8;
 
 pub trait Search {
+    /// Choose a move for `side` among `candidates`, which is never empty
     fn search(
         &mut self,
         board: &BitBoard,
+        side: Side,
         occupied: Occupied,
         candidates: &mut Candidate,
         last_passed: bool,
-    ) -> Position;
+    ) -> SearchResult;
+
+    /// Time allowed for the next search, set by a [`Game`](crate::Game)
+    /// played with a clock
+    ///
+    /// Searchers which can not stop early ignore it.
+    fn set_time_limit(&mut self, _limit: Duration) {}
+}
+
+impl<S: Search + ?Sized> Search for Box<S> {
+    fn search(
+        &mut self,
+        board: &BitBoard,
+        side: Side,
+        occupied: Occupied,
+        candidates: &mut Candidate,
+        last_passed: bool,
+    ) -> SearchResult {
+        (**self).search(board, side, occupied, candidates, last_passed)
+    }
+
+    fn set_time_limit(&mut self, limit: Duration) {
+        (**self).set_time_limit(limit)
+    }
+}
+
+/// Score of a position from the point of view of the side to move
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Evaluation {
+    Exact(CountTurn),
+    Wld(Wld),
+    Estimate(Estimate),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct SearchResult {
+    pub position: Position,
+    pub score: Option<Evaluation>,
+    /// Expected continuation, starting with `position`
+    pub pv: Option<Vec<Move>>,
+    /// Number of visited nodes
+    pub nodes: u64,
+}
+
+impl SearchResult {
+    pub const fn new(position: Position) -> SearchResult {
+        SearchResult {
+            position,
+            score: None,
+            pv: None,
+            nodes: 0,
+        }
+    }
+}
+
+impl From<Position> for SearchResult {
+    fn from(position: Position) -> SearchResult {
+        SearchResult::new(position)
+    }
 }
 
 pub type Turn = u8;
@@ -79,75 +158,41 @@ impl Score for CountTurn {
     }
 }
 
-pub fn search_exact(board: &BitBoard, side: Side, last_passed: bool) -> CountTurn {
-    exact_inner(board, side, last_passed, 0, CountTurn::MIN, CountTurn::MAX)
-}
-
-pub fn search_exact_with_candidates(
-    board: &BitBoard,
-    side: Side,
-    candidates: &mut Candidate,
-    last_passed: bool,
-) -> (Position, CountTurn) {
-    let mut alpha = CountTurn::MIN;
-    let mut best = 0;
-    for pos in candidates {
-        let mut board = board.clone();
-        board.put(side, pos);
-        let a = exact_inner(
-            &board,
-            side.flip(),
-            last_passed,
-            1,
-            CountTurn::MIN,
-            alpha.flip(),
-        )
-        .flip();
-        if a > alpha {
-            alpha = a;
-            best = pos;
+/// Outcome of a game, ordered from the worst to the best
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Wld {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl Wld {
+    pub fn from_counts(mine: u32, opp: u32) -> Wld {
+        use std::cmp::Ordering::*;
+        match mine.cmp(&opp) {
+            Less => Wld::Loss,
+            Equal => Wld::Draw,
+            Greater => Wld::Win,
         }
     }
-    (best, alpha)
-}
-
-fn exact_inner(
-    board: &BitBoard,
-    side: Side,
-    passed: bool,
-    turn: u8,
-    mut alpha: CountTurn,
-    beta: CountTurn,
-) -> CountTurn {
-    let candidates = board.candidates(side);
-    if candidates.len() == 0 {
-        return if passed {
-            let (black, white) = board.count();
-            CountTurn::with_side(side, black, white, turn)
-        } else {
-            exact_inner(board, side.flip(), true, turn, beta.flip(), alpha.flip()).flip()
-        };
+}
+
+impl From<CountTurn> for Wld {
+    fn from(score: CountTurn) -> Wld {
+        Wld::from_counts(score.mine as u32, score.opp as u32)
     }
-    for pos in candidates {
-        let mut board = board.clone();
-        board.put(side, pos);
-        let a = exact_inner(
-            &board,
-            side.flip(),
-            false,
-            turn + 1,
-            beta.flip(),
-            alpha.flip(),
-        )
-        .flip();
-        if a > alpha {
-            alpha = a;
-        }
-        if alpha >= beta {
-            break;
+}
+
+impl Score for Wld {
+    const MIN: Wld = Wld::Loss;
+    const MAX: Wld = Wld::Win;
+    fn flip(&self) -> Wld {
+        match self {
+            Wld::Loss => Wld::Win,
+            Wld::Draw => Wld::Draw,
+            Wld::Win => Wld::Loss,
         }
     }
-    alpha
 }
 
 #[cfg(test)]
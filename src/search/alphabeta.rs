@@ -0,0 +1,354 @@
+use std::{cmp::Reverse, sync::Arc, time::Duration};
+
+use super::{Evaluation, Occupied, Score, Search, SearchResult, StopCondition, Stopper};
+use crate::{notation::Move, BitBoard, Candidate, Position, Side};
+
+/// Value of one disc in [`Estimate`] units
+pub const DISC: i32 = 100;
+
+/// Heuristic score in hundredths of a disc
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Estimate(pub i32);
+
+impl Score for Estimate {
+    const MIN: Estimate = Estimate(-i32::MAX);
+    const MAX: Estimate = Estimate(i32::MAX);
+    fn flip(&self) -> Estimate {
+        Estimate(-self.0)
+    }
+}
+
+/// Static evaluation used at the leaves of a depth-limited search
+pub trait Evaluate {
+    /// Estimated final disc differential for `side`, in [`Estimate`] units
+    fn evaluate(&self, board: &BitBoard, side: Side) -> i32;
+}
+
+impl<E: Evaluate + ?Sized> Evaluate for &E {
+    fn evaluate(&self, board: &BitBoard, side: Side) -> i32 {
+        (**self).evaluate(board, side)
+    }
+}
+
+impl<E: Evaluate + ?Sized> Evaluate for Arc<E> {
+    fn evaluate(&self, board: &BitBoard, side: Side) -> i32 {
+        (**self).evaluate(board, side)
+    }
+}
+
+/// Mobility and corners
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SimpleEvaluator;
+
+const CORNERS: u64 = 0x8100000000000081;
+
+impl Evaluate for SimpleEvaluator {
+    fn evaluate(&self, board: &BitBoard, side: Side) -> i32 {
+        let (mine, opp) = board.mine_opp_keys(side);
+        let blank = !(mine | opp);
+        // 空き隅に隣接する X 打ちは減点
+        let x_squares = [
+            (0x8000000000000000, 0x0040000000000000),
+            (0x0100000000000000, 0x0002000000000000),
+            (0x0000000000000080, 0x0000000000004000),
+            (0x0000000000000001, 0x0000000000000200),
+        ]
+        .iter()
+        .filter(|&&(corner, _)| blank & corner != 0)
+        .fold(0, |x, &(_, square)| x | square);
+        let mobility =
+            board.candidates(side).len() as i32 - board.candidates(side.flip()).len() as i32;
+        let corners = (mine & CORNERS).count_ones() as i32 - (opp & CORNERS).count_ones() as i32;
+        let xs = (mine & x_squares).count_ones() as i32 - (opp & x_squares).count_ones() as i32;
+        mobility * DISC / 2 + corners * 3 * DISC - xs * 3 * DISC / 2
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Limit {
+    /// Iterate up to this depth in plies
+    Depth(u8),
+    /// Iterate deeper while the total number of nodes stays within this budget
+    Nodes(u64),
+}
+
+/// Negamax with principal variation search and iterative deepening
+#[derive(Debug, Clone)]
+pub struct AlphaBetaSearch<E> {
+    evaluator: E,
+    limit: Limit,
+    nodes: u64,
+    aborted: bool,
+    stop: StopCondition,
+    stopper: Stopper,
+}
+
+impl<E> AlphaBetaSearch<E> {
+    pub const fn new(evaluator: E, limit: Limit) -> AlphaBetaSearch<E> {
+        AlphaBetaSearch {
+            evaluator,
+            limit,
+            nodes: 0,
+            aborted: false,
+            stop: StopCondition::new(),
+            stopper: Stopper::new(),
+        }
+    }
+
+    /// Give up when `stop` is met, keeping the result of the deepest
+    /// completed iteration
+    pub fn with_stop(mut self, stop: StopCondition) -> AlphaBetaSearch<E> {
+        self.stop = stop;
+        self
+    }
+
+    pub fn evaluator(&self) -> &E {
+        &self.evaluator
+    }
+}
+
+fn final_score(board: &BitBoard, side: Side) -> Estimate {
+    let (mine, opp) = board.mine_opp_keys(side);
+    Estimate((mine.count_ones() as i32 - opp.count_ones() as i32) * DISC)
+}
+
+impl<E: Evaluate> AlphaBetaSearch<E> {
+    /// Score of every candidate of `side` at the deepest completed depth,
+    /// best first
+    ///
+    /// Unlike [`Search::search`], every move is searched with a full window,
+    /// so the scores of the inferior moves are exact for that depth too.
+    /// When stopped during the first depth, only the moves searched in time
+    /// are returned.
+    pub fn analyze(
+        &mut self,
+        board: &BitBoard,
+        side: Side,
+        occupied: Occupied,
+    ) -> Vec<(Position, Estimate)> {
+        let empties = 64 - occupied;
+        let max_depth = match self.limit {
+            Limit::Depth(depth) => depth.min(empties),
+            Limit::Nodes(_) => empties,
+        };
+        self.nodes = 0;
+        self.aborted = false;
+        self.stopper = self.stop.start(0);
+        let mut board = board.clone();
+        let mut moves = board.candidates(side).collect::<Vec<_>>();
+        let mut scores = Vec::with_capacity(moves.len());
+        let mut pv = Vec::new();
+        for depth in 1..=max_depth.max(1) {
+            let mut next = Vec::with_capacity(moves.len());
+            for &pos in moves.iter() {
+                let full = (Estimate::MIN, Estimate::MAX);
+                let a = self.child(&mut board, side, pos, depth, full, &mut pv);
+                if self.aborted {
+                    break;
+                }
+                next.push((pos, a));
+            }
+            // 途中で止まった深さの結果は最初の深さだけ使う
+            if self.aborted && depth > 1 {
+                break;
+            }
+            next.sort_by_key(|&(_, a)| Reverse(a));
+            scores = next;
+            if self.aborted {
+                break;
+            }
+            moves = scores.iter().map(|&(pos, _)| pos).collect();
+        }
+        scores
+    }
+
+    /// Nodes visited by the last search
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    fn over_budget(&mut self) -> bool {
+        if let Limit::Nodes(budget) = self.limit {
+            if self.nodes > budget {
+                self.aborted = true;
+            }
+        }
+        if self.stopper.check(self.nodes) {
+            self.aborted = true;
+        }
+        self.aborted
+    }
+
+    fn negamax(
+        &mut self,
+        board: &mut BitBoard,
+        side: Side,
+        passed: bool,
+        depth: u8,
+        (alpha, beta): (Estimate, Estimate),
+        pv: &mut Vec<Move>,
+    ) -> Estimate {
+        self.nodes += 1;
+        pv.clear();
+        if self.over_budget() {
+            return alpha;
+        }
+        let candidates = board.candidates(side);
+        if candidates.len() == 0 {
+            return if passed {
+                final_score(board, side)
+            } else {
+                let mut child = Vec::new();
+                let a = self
+                    .negamax(
+                        board,
+                        side.flip(),
+                        true,
+                        depth,
+                        (beta.flip(), alpha.flip()),
+                        &mut child,
+                    )
+                    .flip();
+                pv.push(Move::Pass);
+                pv.append(&mut child);
+                a
+            };
+        }
+        if depth == 0 {
+            return Estimate(self.evaluator.evaluate(board, side));
+        }
+        self.pvs(board, side, candidates, depth, (alpha, beta), pv)
+    }
+
+    /// Score of the child reached by `pos`, seen from `side`
+    fn child(
+        &mut self,
+        board: &mut BitBoard,
+        side: Side,
+        pos: u64,
+        depth: u8,
+        (alpha, beta): (Estimate, Estimate),
+        pv: &mut Vec<Move>,
+    ) -> Estimate {
+        let flips = board.flips(side, pos);
+        board.make(side, pos, flips);
+        let a = self
+            .negamax(
+                board,
+                side.flip(),
+                false,
+                depth - 1,
+                (beta.flip(), alpha.flip()),
+                pv,
+            )
+            .flip();
+        board.unmake(side, pos, flips);
+        a
+    }
+
+    /// Principal variation search over `moves`, which are tried in order
+    fn pvs<I: Iterator<Item = u64>>(
+        &mut self,
+        board: &mut BitBoard,
+        side: Side,
+        moves: I,
+        depth: u8,
+        (mut alpha, beta): (Estimate, Estimate),
+        pv: &mut Vec<Move>,
+    ) -> Estimate {
+        let mut child = Vec::new();
+        let mut best = Estimate::MIN;
+        for (i, pos) in moves.enumerate() {
+            let a = if i == 0 {
+                self.child(board, side, pos, depth, (alpha, beta), &mut child)
+            } else {
+                // null window で十分か確かめてから再探索
+                let null = (alpha, Estimate(alpha.0 + 1));
+                let a = self.child(board, side, pos, depth, null, &mut child);
+                if alpha < a && a < beta && !self.aborted {
+                    self.child(board, side, pos, depth, (alpha, beta), &mut child)
+                } else {
+                    a
+                }
+            };
+            if self.aborted {
+                break;
+            }
+            if a > best {
+                best = a;
+                if a > alpha {
+                    alpha = a;
+                    pv.clear();
+                    pv.push(Move::Put(pos));
+                    pv.append(&mut child);
+                }
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+}
+
+impl<E: Evaluate> Search for AlphaBetaSearch<E> {
+    fn set_time_limit(&mut self, limit: Duration) {
+        self.stop = std::mem::take(&mut self.stop).with_time(limit);
+    }
+
+    fn search(
+        &mut self,
+        board: &BitBoard,
+        side: Side,
+        occupied: Occupied,
+        candidates: &mut Candidate,
+        _last_passed: bool,
+    ) -> SearchResult {
+        let mut moves = candidates.collect::<Vec<_>>();
+        let empties = 64 - occupied;
+        let max_depth = match self.limit {
+            Limit::Depth(depth) => depth.min(empties),
+            Limit::Nodes(_) => empties,
+        };
+        self.nodes = 0;
+        self.aborted = false;
+        self.stopper = self.stop.start(0);
+        // 一手も読み終えずに止まったときは評価値を返さない
+        let mut best = None;
+        let mut pv = Vec::new();
+        for depth in 1..=max_depth.max(1) {
+            let score = self.pvs(
+                &mut board.clone(),
+                side,
+                moves.iter().copied(),
+                depth,
+                (Estimate::MIN, Estimate::MAX),
+                &mut pv,
+            );
+            if self.aborted && depth > 1 {
+                break;
+            }
+            if let Some(&Move::Put(position)) = pv.first() {
+                best = Some((position, score, pv.clone()));
+                // 前回の最善手から読む
+                let i = moves.iter().position(|&p| p == position).unwrap();
+                moves[..=i].rotate_right(1);
+            }
+            if self.aborted {
+                break;
+            }
+        }
+        match best {
+            Some((position, score, pv)) => SearchResult {
+                position,
+                score: Some(Evaluation::Estimate(score)),
+                pv: Some(pv),
+                nodes: self.nodes,
+            },
+            None => SearchResult {
+                nodes: self.nodes,
+                ..SearchResult::new(moves[0])
+            },
+        }
+    }
+}
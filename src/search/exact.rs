@@ -0,0 +1,792 @@
+use std::{cmp::Reverse, fmt, time::Duration};
+
+use super::{CountTurn, Score, StopCondition, Stopper, Wld};
+use crate::{notation::Move, BitBoard, Candidate, Position, Side};
+
+/// Nodes with fewer empties are cheaper to search than to look up
+const TABLE_MIN_EMPTIES: u8 = 5;
+/// Nodes with fewer empties are searched in raw bit order
+const ORDERING_MIN_EMPTIES: u8 = 6;
+/// No position has more legal moves than this
+const MAX_MOVES: usize = 34;
+
+const CORNERS: u64 = 0x8100000000000081;
+const QUADRANTS: [u64; 4] = [
+    0xf0f0f0f000000000,
+    0x0f0f0f0f00000000,
+    0x00000000f0f0f0f0,
+    0x000000000f0f0f0f,
+];
+
+/// Heuristics used to sort moves before searching them
+///
+/// Moves are sorted by ascending opponent mobility (fastest-first), with
+/// bonuses for corners and for moves in a region with an odd number of
+/// empties (parity). The hash move, if any, always goes first.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct MoveOrdering {
+    pub hash_move: bool,
+    pub fastest_first: bool,
+    pub corners: bool,
+    pub parity: bool,
+}
+
+impl MoveOrdering {
+    pub const NONE: MoveOrdering = MoveOrdering {
+        hash_move: false,
+        fastest_first: false,
+        corners: false,
+        parity: false,
+    };
+    pub const ALL: MoveOrdering = MoveOrdering {
+        hash_move: true,
+        fastest_first: true,
+        corners: true,
+        parity: true,
+    };
+
+    const fn sorts(&self) -> bool {
+        self.fastest_first || self.corners || self.parity
+    }
+}
+
+impl Default for MoveOrdering {
+    /// Only the hash move, which needs a transposition table to matter
+    fn default() -> MoveOrdering {
+        MoveOrdering {
+            hash_move: true,
+            ..MoveOrdering::NONE
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+/// `mine == opp == 0` marks an unused entry
+#[derive(Debug, Copy, Clone)]
+struct Entry {
+    mine: u64,
+    opp: u64,
+    /// `turn` is relative to the stored node
+    score: CountTurn,
+    bound: Bound,
+    best: Position,
+    empties: u8,
+}
+
+const EMPTY_ENTRY: Entry = Entry {
+    mine: 0,
+    opp: 0,
+    score: CountTurn::new(0, 0, 0),
+    bound: Bound::Exact,
+    best: 0,
+    empties: 0,
+};
+
+/// Bounds proven by the win/loss/draw search, `lower == upper` once exact
+#[derive(Debug, Copy, Clone)]
+struct WldEntry {
+    mine: u64,
+    opp: u64,
+    lower: Wld,
+    upper: Wld,
+    best: Position,
+    empties: u8,
+}
+
+const EMPTY_WLD_ENTRY: WldEntry = WldEntry {
+    mine: 0,
+    opp: 0,
+    lower: Wld::Loss,
+    upper: Wld::Win,
+    best: 0,
+    empties: 0,
+};
+
+fn hash(mine: u64, opp: u64) -> u64 {
+    let h = mine.wrapping_mul(0x9e3779b97f4a7c15) ^ opp.wrapping_mul(0xc2b2ae3d27d4eb4f);
+    h ^ (h >> 29)
+}
+
+/// Exact endgame solver with an optional transposition table
+///
+/// The table is keyed on the discs of the side to move and of the opponent,
+/// so it stays valid across calls and can be reused for the whole game.
+/// Win/loss/draw searches keep their bounds in a second table of the same
+/// size, and also use the exact scores of the first one.
+#[derive(Clone)]
+pub struct ExactSolver {
+    table: Vec<Entry>,
+    wld_table: Vec<WldEntry>,
+    table_size: usize,
+    symmetric: bool,
+    ordering: MoveOrdering,
+    nodes: u64,
+    stop: StopCondition,
+    stopper: Stopper,
+}
+
+impl fmt::Debug for ExactSolver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ExactSolver")
+            .field("table_size", &self.table_size)
+            .field("symmetric", &self.symmetric)
+            .field("ordering", &self.ordering)
+            .field("nodes", &self.nodes)
+            .field("stop", &self.stop)
+            .finish()
+    }
+}
+
+impl ExactSolver {
+    /// Solver with a table of `table_size` entries, rounded down to a power
+    /// of two; `0` disables the table
+    ///
+    /// The tables are allocated on first use.
+    pub const fn new(table_size: usize) -> ExactSolver {
+        let table_size = match table_size {
+            0 => 0,
+            n => 1 << (usize::BITS - 1 - n.leading_zeros()),
+        };
+        ExactSolver {
+            table: Vec::new(),
+            wld_table: Vec::new(),
+            table_size,
+            symmetric: false,
+            ordering: MoveOrdering {
+                hash_move: true,
+                ..MoveOrdering::NONE
+            },
+            nodes: 0,
+            stop: StopCondition::new(),
+            stopper: Stopper::new(),
+        }
+    }
+
+    pub const fn with_ordering(mut self, ordering: MoveOrdering) -> ExactSolver {
+        self.ordering = ordering;
+        self
+    }
+
+    /// Share table entries between the 8 symmetric images of a position
+    ///
+    /// Saves table space at the cost of computing [`BitBoard::unique`] at
+    /// every stored node.
+    pub const fn with_symmetry(mut self, symmetric: bool) -> ExactSolver {
+        self.symmetric = symmetric;
+        self
+    }
+
+    /// Give up when `stop` is met; see [`ExactSolver::stopped`]
+    pub fn with_stop(mut self, stop: StopCondition) -> ExactSolver {
+        self.stop = stop;
+        self
+    }
+
+    /// Replace the time limit of the stop condition
+    pub fn set_time_limit(&mut self, limit: Duration) {
+        self.stop = std::mem::take(&mut self.stop).with_time(limit);
+    }
+
+    pub const fn table_size(&self) -> usize {
+        self.table_size
+    }
+
+    /// Nodes visited since the solver was created
+    pub const fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    pub fn clear(&mut self) {
+        self.table.clear();
+        self.wld_table.clear();
+        self.nodes = 0;
+    }
+
+    /// Whether the last call was cut short by the stop condition
+    ///
+    /// Scores are then bounds at best. Methods returning a move return the
+    /// best one among those completely solved, or the first candidate.
+    pub fn stopped(&self) -> bool {
+        self.stopper.stopped()
+    }
+
+    pub(super) fn start(&mut self) {
+        self.stopper = self.stop.start(self.nodes);
+    }
+
+    pub fn solve(&mut self, board: &BitBoard, side: Side, last_passed: bool) -> CountTurn {
+        self.start();
+        let mut board = board.clone();
+        self.inner(
+            &mut board,
+            side,
+            last_passed,
+            0,
+            CountTurn::MIN,
+            CountTurn::MAX,
+        )
+    }
+
+    /// Score and a principal variation leading to the end of the game
+    ///
+    /// Passes are included in the variation, except when the game ends
+    /// because neither side can move. Each move is chosen as the first one
+    /// whose exact score equals the score of its parent, so the variation is
+    /// one of possibly many perfect lines.
+    pub fn solve_pv(
+        &mut self,
+        board: &BitBoard,
+        side: Side,
+        last_passed: bool,
+    ) -> (CountTurn, Vec<Move>) {
+        use std::cmp::Ordering::Equal;
+        let score = self.solve(board, side, last_passed);
+        let mut pv = Vec::new();
+        let (mut board, mut side, mut passed) = (board.clone(), side, last_passed);
+        let mut target = score;
+        let mut turn = 0;
+        while !self.stopped() {
+            let candidates = board.candidates(side);
+            if candidates.len() == 0 {
+                if passed || board.candidates(side.flip()).len() == 0 {
+                    break;
+                }
+                pv.push(Move::Pass);
+                side = side.flip();
+                passed = true;
+                target = target.flip();
+                continue;
+            }
+            let mut found = None;
+            for pos in candidates {
+                let flips = board.flips(side, pos);
+                board.make(side, pos, flips);
+                let a = self
+                    .inner(
+                        &mut board,
+                        side.flip(),
+                        false,
+                        turn + 1,
+                        CountTurn::MIN,
+                        CountTurn::MAX,
+                    )
+                    .flip();
+                board.unmake(side, pos, flips);
+                if a.cmp(&target) == Equal {
+                    found = Some(pos);
+                    break;
+                }
+            }
+            let pos = match found {
+                Some(pos) => pos,
+                None if self.stopped() => break,
+                None => panic!("no move reaches the solved score"),
+            };
+            board.put(side, pos);
+            pv.push(Move::Put(pos));
+            side = side.flip();
+            passed = false;
+            target = target.flip();
+            turn += 1;
+        }
+        (score, pv)
+    }
+
+    /// Best move among `candidates`, which must not be empty, and its score
+    pub fn solve_with_candidates(
+        &mut self,
+        board: &BitBoard,
+        side: Side,
+        candidates: &mut Candidate,
+    ) -> (Position, CountTurn) {
+        self.start();
+        let mut alpha = CountTurn::MIN;
+        let mut best = None;
+        for pos in candidates {
+            let a = self.solve_move(board, side, pos, alpha);
+            if self.stopped() {
+                best = best.or(Some(pos));
+                break;
+            }
+            if best.is_none() || a > alpha {
+                alpha = a;
+                best = Some(pos);
+            }
+        }
+        (best.expect("no candidates"), alpha)
+    }
+
+    /// Exact score of every candidate of `side`, best first
+    ///
+    /// Moves with the same score keep their candidate order. When stopped,
+    /// only the moves solved in time are returned.
+    pub fn analyze(&mut self, board: &BitBoard, side: Side) -> Vec<(Position, CountTurn)> {
+        self.start();
+        let mut scores = Vec::new();
+        for pos in board.candidates(side) {
+            let a = self.solve_move(board, side, pos, CountTurn::MIN);
+            if self.stopped() {
+                break;
+            }
+            scores.push((pos, a));
+        }
+        scores.sort_by_key(|&(_, a)| Reverse(a));
+        scores
+    }
+
+    /// Score of `pos` for `side`, exact if it is greater than `alpha`
+    pub(super) fn solve_move(
+        &mut self,
+        board: &BitBoard,
+        side: Side,
+        pos: Position,
+        alpha: CountTurn,
+    ) -> CountTurn {
+        let mut board = board.clone();
+        board.put(side, pos);
+        self.inner(
+            &mut board,
+            side.flip(),
+            false,
+            1,
+            CountTurn::MIN,
+            alpha.flip(),
+        )
+        .flip()
+    }
+
+    fn key(&self, board: &BitBoard, side: Side) -> (u64, u64, u32) {
+        let (mine, opp) = board.mine_opp_keys(side);
+        if self.symmetric {
+            let (unique, flag) = BitBoard::from_masks(mine, opp).unique_with_flag();
+            (unique.black(), unique.white(), flag)
+        } else {
+            (mine, opp, 0)
+        }
+    }
+
+    fn slot(&self, mine: u64, opp: u64) -> usize {
+        // 2 エントリで 1 バケット: 深さ優先と常に上書き
+        hash(mine, opp) as usize & (self.table_size - 1) & !1
+    }
+
+    fn probe(
+        &mut self,
+        board: &BitBoard,
+        side: Side,
+        turn: u8,
+    ) -> Option<(CountTurn, Bound, Position)> {
+        if self.table.is_empty() {
+            self.table = vec![EMPTY_ENTRY; self.table_size];
+        }
+        let (mine, opp, flag) = self.key(board, side);
+        let i = self.slot(mine, opp);
+        self.table[i..i + 2]
+            .iter()
+            .find(|e| e.mine == mine && e.opp == opp)
+            .map(|e| {
+                let mut score = e.score;
+                score.turn = score.turn.wrapping_add(turn);
+                (
+                    score,
+                    e.bound,
+                    BitBoard::inverse_symmetry_mask(e.best, flag),
+                )
+            })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn store(
+        &mut self,
+        board: &BitBoard,
+        side: Side,
+        turn: u8,
+        empties: u8,
+        mut score: CountTurn,
+        bound: Bound,
+        best: Position,
+    ) {
+        let (mine, opp, flag) = self.key(board, side);
+        score.turn = score.turn.wrapping_sub(turn);
+        let entry = Entry {
+            mine,
+            opp,
+            score,
+            bound,
+            best: BitBoard::symmetry_mask(best, flag),
+            empties,
+        };
+        let i = self.slot(mine, opp);
+        let deep = &self.table[i];
+        if deep.empties <= empties || (deep.mine == mine && deep.opp == opp) {
+            self.table[i] = entry;
+        } else {
+            self.table[i + 1] = entry;
+        }
+    }
+
+    /// Sort key of a move, smaller is searched first
+    fn move_key(
+        &self,
+        board: &mut BitBoard,
+        side: Side,
+        pos: Position,
+        hash_move: Position,
+        parity: u64,
+    ) -> i32 {
+        if pos == hash_move {
+            return i32::MIN;
+        }
+        let mut key = 0;
+        if self.ordering.fastest_first {
+            let flips = board.flips(side, pos);
+            board.make(side, pos, flips);
+            key += 16 * board.candidates(side.flip()).len() as i32;
+            board.unmake(side, pos, flips);
+        }
+        if self.ordering.corners && pos & CORNERS != 0 {
+            key -= 32;
+        }
+        if self.ordering.parity && pos & parity != 0 {
+            key -= 8;
+        }
+        key
+    }
+
+    /// Candidates in search order, in the first `len` slots of the array
+    fn order(
+        &self,
+        board: &mut BitBoard,
+        side: Side,
+        candidates: Candidate,
+        mut hash_move: Position,
+        empties: u8,
+    ) -> ([(i32, Position); MAX_MOVES], usize) {
+        if !self.ordering.hash_move || !candidates.contains(hash_move) {
+            hash_move = 0;
+        }
+        let mut moves = [(0, 0); MAX_MOVES];
+        let mut len = 0;
+        if empties >= ORDERING_MIN_EMPTIES && self.ordering.sorts() {
+            let parity = QUADRANTS
+                .iter()
+                .filter(|&&q| (q & !(board.black() | board.white())).count_ones() % 2 == 1)
+                .fold(0, |p, &q| p | q);
+            for pos in candidates {
+                moves[len] = (self.move_key(board, side, pos, hash_move, parity), pos);
+                len += 1;
+            }
+            moves[..len].sort_unstable();
+        } else {
+            if hash_move != 0 {
+                moves[0] = (0, hash_move);
+                len = 1;
+            }
+            for pos in candidates.filter(|&p| p != hash_move) {
+                moves[len] = (0, pos);
+                len += 1;
+            }
+        }
+        (moves, len)
+    }
+
+    fn inner(
+        &mut self,
+        board: &mut BitBoard,
+        side: Side,
+        passed: bool,
+        turn: u8,
+        mut alpha: CountTurn,
+        beta: CountTurn,
+    ) -> CountTurn {
+        self.nodes += 1;
+        if self.stopper.check(self.nodes) {
+            return alpha;
+        }
+        let candidates = board.candidates(side);
+        if candidates.len() == 0 {
+            return if passed {
+                let (black, white) = board.count();
+                CountTurn::with_side(side, black, white, turn)
+            } else {
+                self.inner(board, side.flip(), true, turn, beta.flip(), alpha.flip())
+                    .flip()
+            };
+        }
+        let (black, white) = board.count();
+        let empties = 64 - black - white;
+        let use_table = self.table_size > 1 && empties >= TABLE_MIN_EMPTIES;
+        let mut hash_move = 0;
+        if use_table {
+            if let Some((score, bound, best)) = self.probe(board, side, turn) {
+                match bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return alpha,
+                    _ => (),
+                }
+                hash_move = best;
+            }
+        }
+        let (moves, len) = self.order(board, side, candidates, hash_move, empties);
+        let alpha_orig = alpha;
+        let mut best = 0;
+        for &(_, pos) in moves[..len].iter() {
+            let flips = board.flips(side, pos);
+            board.make(side, pos, flips);
+            let a = self
+                .inner(
+                    board,
+                    side.flip(),
+                    false,
+                    turn + 1,
+                    beta.flip(),
+                    alpha.flip(),
+                )
+                .flip();
+            board.unmake(side, pos, flips);
+            if self.stopper.stopped() {
+                return alpha;
+            }
+            if a > alpha {
+                alpha = a;
+                best = pos;
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        if use_table {
+            let bound = if alpha >= beta {
+                Bound::Lower
+            } else if alpha > alpha_orig {
+                Bound::Exact
+            } else {
+                Bound::Upper
+            };
+            self.store(board, side, turn, empties, alpha, bound, best);
+        }
+        alpha
+    }
+}
+
+/// Win/loss/draw only search
+impl ExactSolver {
+    /// Prove whether `side` wins, loses or draws, without the exact count
+    ///
+    /// Two null-window searches around the draw: the first one only proves
+    /// whether `side` wins, the second one, reusing the table, whether it
+    /// at least draws. Returns `None` when the stop condition cuts either
+    /// search short, since the outcome is then unproven.
+    pub fn solve_wld(&mut self, board: &BitBoard, side: Side, last_passed: bool) -> Option<Wld> {
+        self.start();
+        let mut board = board.clone();
+        let wld = match self.wld_inner(&mut board, side, last_passed, Wld::Draw, Wld::Win) {
+            Wld::Win => Wld::Win,
+            _ if self.stopped() => return None,
+            _ => self.wld_inner(&mut board, side, last_passed, Wld::Loss, Wld::Draw),
+        };
+        (!self.stopped()).then_some(wld)
+    }
+
+    /// A move among `candidates` achieving the best outcome, and the outcome
+    pub fn solve_wld_with_candidates(
+        &mut self,
+        board: &BitBoard,
+        side: Side,
+        candidates: &mut Candidate,
+    ) -> (Position, Wld) {
+        self.start();
+        let mut board = board.clone();
+        let mut alpha = Wld::Loss;
+        let mut best = None;
+        for pos in candidates {
+            let flips = board.flips(side, pos);
+            board.make(side, pos, flips);
+            let a = self
+                .wld_inner(&mut board, side.flip(), false, Wld::Loss, alpha.flip())
+                .flip();
+            board.unmake(side, pos, flips);
+            if self.stopped() {
+                best = best.or(Some(pos));
+                break;
+            }
+            if best.is_none() || a > alpha {
+                alpha = a;
+                best = Some(pos);
+            }
+            if alpha == Wld::Win {
+                break;
+            }
+        }
+        (best.expect("no candidates"), alpha)
+    }
+
+    fn wld_inner(
+        &mut self,
+        board: &mut BitBoard,
+        side: Side,
+        passed: bool,
+        mut alpha: Wld,
+        beta: Wld,
+    ) -> Wld {
+        self.nodes += 1;
+        if self.stopper.check(self.nodes) {
+            return alpha;
+        }
+        let candidates = board.candidates(side);
+        if candidates.len() == 0 {
+            return if passed {
+                let (mine, opp) = board.mine_opp_keys(side);
+                Wld::from_counts(mine.count_ones(), opp.count_ones())
+            } else {
+                self.wld_inner(board, side.flip(), true, beta.flip(), alpha.flip())
+                    .flip()
+            };
+        }
+        let (black, white) = board.count();
+        let empties = 64 - black - white;
+        let use_table = self.table_size > 1 && empties >= TABLE_MIN_EMPTIES;
+        let mut hash_move = 0;
+        if use_table {
+            let (lower, upper, best) = self.wld_probe(board, side);
+            if lower >= beta || lower == upper {
+                return lower;
+            }
+            if upper <= alpha {
+                return alpha;
+            }
+            hash_move = best;
+        }
+        let (moves, len) = self.order(board, side, candidates, hash_move, empties);
+        let alpha_orig = alpha;
+        let mut best = 0;
+        for &(_, pos) in moves[..len].iter() {
+            let flips = board.flips(side, pos);
+            board.make(side, pos, flips);
+            let a = self
+                .wld_inner(board, side.flip(), false, beta.flip(), alpha.flip())
+                .flip();
+            board.unmake(side, pos, flips);
+            if self.stopper.stopped() {
+                return alpha;
+            }
+            if a > alpha {
+                alpha = a;
+                best = pos;
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        if use_table {
+            let (lower, upper) = if alpha >= beta {
+                (alpha, Wld::Win)
+            } else if alpha > alpha_orig {
+                (alpha, alpha)
+            } else {
+                (Wld::Loss, alpha)
+            };
+            self.wld_store(board, side, empties, lower, upper, best);
+        }
+        alpha
+    }
+
+    /// Bounds known for the position, from either table, and the best move
+    fn wld_probe(&mut self, board: &BitBoard, side: Side) -> (Wld, Wld, Position) {
+        if self.wld_table.is_empty() {
+            self.wld_table = vec![EMPTY_WLD_ENTRY; self.table_size];
+        }
+        let (mut lower, mut upper, mut best) = (Wld::Loss, Wld::Win, 0);
+        // 石数の探索結果も勝敗の範囲として使える
+        let exact = if self.table.is_empty() {
+            None
+        } else {
+            self.probe(board, side, 0)
+        };
+        if let Some((score, bound, pos)) = exact {
+            match bound {
+                Bound::Exact => return (score.into(), score.into(), pos),
+                Bound::Lower => lower = score.into(),
+                Bound::Upper => upper = score.into(),
+            }
+            best = pos;
+        }
+        let (mine, opp, flag) = self.key(board, side);
+        let i = self.slot(mine, opp);
+        if let Some(e) = self.wld_table[i..i + 2]
+            .iter()
+            .find(|e| e.mine == mine && e.opp == opp)
+        {
+            lower = lower.max(e.lower);
+            upper = upper.min(e.upper);
+            best = BitBoard::inverse_symmetry_mask(e.best, flag);
+        }
+        (lower, upper, best)
+    }
+
+    fn wld_store(
+        &mut self,
+        board: &BitBoard,
+        side: Side,
+        empties: u8,
+        mut lower: Wld,
+        mut upper: Wld,
+        best: Position,
+    ) {
+        let (mine, opp, flag) = self.key(board, side);
+        let i = self.slot(mine, opp);
+        let j = match self.wld_table[i..i + 2]
+            .iter()
+            .position(|e| e.mine == mine && e.opp == opp)
+        {
+            Some(k) => {
+                // 同じ局面の既存の範囲と合わせる
+                let e = &self.wld_table[i + k];
+                lower = lower.max(e.lower);
+                upper = upper.min(e.upper);
+                i + k
+            }
+            None if self.wld_table[i].empties <= empties => i,
+            None => i + 1,
+        };
+        self.wld_table[j] = WldEntry {
+            mine,
+            opp,
+            lower,
+            upper,
+            best: BitBoard::symmetry_mask(best, flag),
+            empties,
+        };
+    }
+}
+
+pub fn search_exact(board: &BitBoard, side: Side, last_passed: bool) -> CountTurn {
+    ExactSolver::new(0).solve(board, side, last_passed)
+}
+
+pub fn search_exact_pv(board: &BitBoard, side: Side, last_passed: bool) -> (CountTurn, Vec<Move>) {
+    ExactSolver::new(1 << 16)
+        .with_ordering(MoveOrdering::ALL)
+        .solve_pv(board, side, last_passed)
+}
+
+pub fn search_wld(board: &BitBoard, side: Side, last_passed: bool) -> Wld {
+    ExactSolver::new(0)
+        .solve_wld(board, side, last_passed)
+        .expect("stopped without a stop condition")
+}
+
+pub fn search_exact_with_candidates(
+    board: &BitBoard,
+    side: Side,
+    candidates: &mut Candidate,
+) -> (Position, CountTurn) {
+    ExactSolver::new(0).solve_with_candidates(board, side, candidates)
+}
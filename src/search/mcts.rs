@@ -0,0 +1,322 @@
+use std::time::{Duration, Instant};
+
+use super::{Occupied, Search, SearchResult, StopCondition, Wld};
+use crate::{notation::Move, BitBoard, Candidate, Side};
+
+/// How long [`MctsSearch`] keeps playing out
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Budget {
+    /// Number of playouts per move, counting those of a reused tree
+    Playouts(u64),
+    /// Wall-clock time per move
+    Time(Duration),
+}
+
+/// Plies below the previous root searched for the new position when the tree
+/// is reused
+///
+/// Four covers our move, their move, our pass and their move again.
+const REUSE_DEPTH: usize = 4;
+
+#[derive(Debug, Clone)]
+struct Node {
+    board: BitBoard,
+    side: Side,
+    /// The move that led here, played by `side.flip()`
+    mv: Move,
+    passed: bool,
+    children: Vec<usize>,
+    untried: Vec<Move>,
+    visits: u64,
+    /// Sum of the rewards of `side.flip()`: 1 for a win and 0.5 for a draw
+    wins: f64,
+}
+
+impl Node {
+    fn new(board: BitBoard, side: Side, mv: Move, passed: bool) -> Node {
+        let candidates = board.candidates(side);
+        let untried = if candidates.len() > 0 {
+            candidates.map(Move::Put).collect()
+        } else if passed || board.candidates(side.flip()).len() == 0 {
+            Vec::new()
+        } else {
+            vec![Move::Pass]
+        };
+        Node {
+            board,
+            side,
+            mv,
+            passed,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            wins: 0.0,
+        }
+    }
+}
+
+/// Monte Carlo tree search with UCT selection
+///
+/// Playouts choose their moves with `policy`, e.g. a
+/// [`RandomSearch`](super::RandomSearch) for uniformly random playouts.
+#[derive(Debug, Clone)]
+pub struct MctsSearch<P> {
+    policy: P,
+    budget: Budget,
+    exploration: f64,
+    reuse: bool,
+    stop: StopCondition,
+    tree: Vec<Node>,
+    playouts: u64,
+}
+
+impl<P> MctsSearch<P> {
+    pub const fn new(policy: P, budget: Budget) -> MctsSearch<P> {
+        MctsSearch {
+            policy,
+            budget,
+            exploration: std::f64::consts::SQRT_2,
+            reuse: false,
+            stop: StopCondition::new(),
+            tree: Vec::new(),
+            playouts: 0,
+        }
+    }
+
+    /// Exploration constant of UCB1, `sqrt(2)` by default
+    pub const fn with_exploration(mut self, exploration: f64) -> MctsSearch<P> {
+        self.exploration = exploration;
+        self
+    }
+
+    /// Keep the subtree of the position reached between successive searches
+    pub const fn with_reuse(mut self, reuse: bool) -> MctsSearch<P> {
+        self.reuse = reuse;
+        self
+    }
+
+    /// Stop early when `stop` is met, counting playouts as nodes
+    pub fn with_stop(mut self, stop: StopCondition) -> MctsSearch<P> {
+        self.stop = stop;
+        self
+    }
+
+    /// Playouts behind the last result, including those of a reused tree
+    pub fn playouts(&self) -> u64 {
+        self.playouts
+    }
+
+    /// Discard the kept tree
+    pub fn clear(&mut self) {
+        self.tree.clear();
+    }
+
+    fn find(&self, board: &BitBoard, side: Side) -> Option<usize> {
+        let mut stack = vec![(0, 0)];
+        while let Some((i, depth)) = stack.pop() {
+            let node = &self.tree[i];
+            if depth > 0 && node.side == side && node.board == *board {
+                return Some(i);
+            }
+            if depth < REUSE_DEPTH {
+                stack.extend(node.children.iter().map(|&c| (c, depth + 1)));
+            }
+        }
+        None
+    }
+
+    /// Move the subtree of `root` to the front of the arena
+    fn reroot(&mut self, root: usize) {
+        let mut old = std::mem::take(&mut self.tree)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        let mut stack: Vec<(usize, Option<usize>)> = vec![(root, None)];
+        while let Some((i, parent)) = stack.pop() {
+            let mut node = old[i].take().unwrap();
+            let children = std::mem::take(&mut node.children);
+            let j = self.tree.len();
+            self.tree.push(node);
+            if let Some(p) = parent {
+                self.tree[p].children.push(j);
+            }
+            stack.extend(children.into_iter().rev().map(|c| (c, Some(j))));
+        }
+    }
+
+    fn select(&self, i: usize) -> usize {
+        let node = &self.tree[i];
+        let log = (node.visits as f64).ln();
+        let ucb = |c: usize| {
+            let child = &self.tree[c];
+            child.wins / child.visits as f64 + self.exploration * (log / child.visits as f64).sqrt()
+        };
+        *node
+            .children
+            .iter()
+            .max_by(|&&a, &&b| ucb(a).partial_cmp(&ucb(b)).unwrap())
+            .unwrap()
+    }
+
+    fn expand(&mut self, i: usize) -> usize {
+        let node = &mut self.tree[i];
+        let mv = node.untried.pop().unwrap();
+        let mut board = node.board.clone();
+        let side = node.side;
+        if let Move::Put(pos) = mv {
+            let flips = board.flips(side, pos);
+            board.make(side, pos, flips);
+        }
+        let child = Node::new(board, side.flip(), mv, mv == Move::Pass);
+        let j = self.tree.len();
+        self.tree.push(child);
+        self.tree[i].children.push(j);
+        j
+    }
+
+    fn best_child(&self, i: usize) -> usize {
+        *self.tree[i]
+            .children
+            .iter()
+            .max_by_key(|&&c| self.tree[c].visits)
+            .unwrap()
+    }
+}
+
+impl<P: Search> MctsSearch<P> {
+    /// Play `board` out with the policy and return the reward of `side`
+    fn playout(&mut self, board: &BitBoard, side: Side, passed: bool) -> f64 {
+        let mut board = board.clone();
+        let (mut turn, mut passed) = (side, passed);
+        loop {
+            let mut candidates = board.candidates(turn);
+            if candidates.len() == 0 {
+                if passed {
+                    break;
+                }
+                turn = turn.flip();
+                passed = true;
+                continue;
+            }
+            let (b, w) = board.count();
+            let pos = self
+                .policy
+                .search(&board, turn, b + w, &mut candidates, passed)
+                .position;
+            let flips = board.flips(turn, pos);
+            board.make(turn, pos, flips);
+            turn = turn.flip();
+            passed = false;
+        }
+        let (b, w) = board.count();
+        let (mine, opp) = match side {
+            Side::Black => (b, w),
+            Side::White => (w, b),
+        };
+        match Wld::from_counts(mine as u32, opp as u32) {
+            Wld::Loss => 0.0,
+            Wld::Draw => 0.5,
+            Wld::Win => 1.0,
+        }
+    }
+
+    fn iterate(&mut self) {
+        let mut path = vec![0];
+        let mut i = 0;
+        while self.tree[i].untried.is_empty() && !self.tree[i].children.is_empty() {
+            i = self.select(i);
+            path.push(i);
+        }
+        if !self.tree[i].untried.is_empty() {
+            i = self.expand(i);
+            path.push(i);
+        }
+        let node = &self.tree[i];
+        let (board, side, passed) = (node.board.clone(), node.side, node.passed);
+        // reward of the player who moved into the leaf
+        let mut reward = 1.0 - self.playout(&board, side, passed);
+        for &i in path.iter().rev() {
+            let node = &mut self.tree[i];
+            node.visits += 1;
+            node.wins += reward;
+            reward = 1.0 - reward;
+        }
+    }
+}
+
+impl<P: Search> Search for MctsSearch<P> {
+    fn set_time_limit(&mut self, limit: Duration) {
+        self.stop = std::mem::take(&mut self.stop).with_time(limit);
+    }
+
+    fn search(
+        &mut self,
+        board: &BitBoard,
+        side: Side,
+        _occupied: Occupied,
+        candidates: &mut Candidate,
+        last_passed: bool,
+    ) -> SearchResult {
+        let start = Instant::now();
+        let root = if self.reuse && !self.tree.is_empty() {
+            self.find(board, side)
+        } else {
+            None
+        };
+        match root {
+            Some(root) => self.reroot(root),
+            None => {
+                self.tree.clear();
+                self.tree
+                    .push(Node::new(board.clone(), side, Move::Pass, last_passed));
+            }
+        }
+        // 根では渡された候補手だけを試す
+        let allowed = |mv: Move| match mv {
+            Move::Put(position) => candidates.contains(position),
+            Move::Pass => false,
+        };
+        self.tree[0].untried.retain(|&mv| allowed(mv));
+        let children = std::mem::take(&mut self.tree[0].children);
+        self.tree[0].children = children
+            .into_iter()
+            .filter(|&c| allowed(self.tree[c].mv))
+            .collect();
+        let before = self.tree[0].visits;
+        let mut stopper = self.stop.start(0);
+        loop {
+            // 停止条件は数えたプレイアウトで判定し、少なくとも一手は試す
+            if stopper.poll(self.tree[0].visits - before) && !self.tree[0].children.is_empty() {
+                break;
+            }
+            let done = match self.budget {
+                Budget::Playouts(n) => self.tree[0].visits >= n,
+                Budget::Time(limit) => start.elapsed() >= limit,
+            };
+            // 候補手をすべて一度は試す
+            if done && self.tree[0].untried.is_empty() {
+                break;
+            }
+            self.iterate();
+        }
+        self.playouts = self.tree[0].visits;
+        let mut pv = Vec::new();
+        let mut i = 0;
+        while !self.tree[i].children.is_empty() {
+            i = self.best_child(i);
+            pv.push(self.tree[i].mv);
+        }
+        let position = match pv[0] {
+            Move::Put(position) => position,
+            Move::Pass => unreachable!("searched a position without candidates"),
+        };
+        if !self.reuse {
+            self.tree.clear();
+        }
+        SearchResult {
+            pv: Some(pv),
+            nodes: self.playouts - before,
+            ..SearchResult::new(position)
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use super::{CountTurn, ExactSolver, Score};
+use crate::{BitBoard, Candidate, Position, Side};
+
+/// Exact solver splitting the root moves over several threads
+///
+/// The first move is solved alone (young brothers wait), then the other
+/// moves are handed out to the threads, which share the best score found so
+/// far as their lower bound. Each thread owns a copy of the given
+/// [`ExactSolver`] and its transposition table.
+///
+/// The score is the same as with [`ExactSolver`]. When several moves share
+/// the best score, the returned move may differ. The stop condition of the
+/// given solver applies to each thread on its own.
+#[derive(Debug, Clone)]
+pub struct ParallelSolver {
+    solvers: Vec<ExactSolver>,
+}
+
+impl ParallelSolver {
+    /// # Panics
+    ///
+    /// Panics if `threads` is 0.
+    pub fn new(threads: usize, solver: ExactSolver) -> ParallelSolver {
+        assert!(threads > 0, "at least one thread is required");
+        ParallelSolver {
+            solvers: vec![solver; threads],
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.solvers.len()
+    }
+
+    /// Nodes visited by all threads
+    pub fn nodes(&self) -> u64 {
+        self.solvers.iter().map(|s| s.nodes()).sum()
+    }
+
+    /// Whether any thread was cut short by its stop condition
+    pub fn stopped(&self) -> bool {
+        self.solvers.iter().any(|s| s.stopped())
+    }
+
+    pub fn clear(&mut self) {
+        for solver in self.solvers.iter_mut() {
+            solver.clear();
+        }
+    }
+
+    pub fn solve(&mut self, board: &BitBoard, side: Side, last_passed: bool) -> CountTurn {
+        let mut candidates = board.candidates(side);
+        if candidates.len() == 0 {
+            self.solvers[0].solve(board, side, last_passed)
+        } else {
+            self.solve_with_candidates(board, side, &mut candidates).1
+        }
+    }
+
+    /// Best move among `candidates`, which must not be empty, and its score
+    pub fn solve_with_candidates(
+        &mut self,
+        board: &BitBoard,
+        side: Side,
+        candidates: &mut Candidate,
+    ) -> (Position, CountTurn) {
+        let moves = candidates.collect::<Vec<_>>();
+        let first = *moves.first().expect("no candidates");
+        for solver in self.solvers.iter_mut() {
+            solver.start();
+        }
+        let first_score = self.solvers[0].solve_move(board, side, first, CountTurn::MIN);
+        let alpha = Mutex::new(first_score);
+        let next = AtomicUsize::new(1);
+        // (手の番号, 評価値) 探索時の alpha を超えたものだけが正確な値
+        if self.solvers[0].stopped() {
+            return (first, first_score);
+        }
+        let exact = Mutex::new(vec![(0, first_score)]);
+        thread::scope(|scope| {
+            for solver in self.solvers.iter_mut() {
+                let (alpha, next, exact, moves) = (&alpha, &next, &exact, &moves);
+                scope.spawn(move || loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    if i >= moves.len() {
+                        break;
+                    }
+                    let lower = *alpha.lock().unwrap();
+                    let a = solver.solve_move(board, side, moves[i], lower);
+                    if solver.stopped() {
+                        break;
+                    }
+                    if a > lower {
+                        exact.lock().unwrap().push((i, a));
+                        let mut alpha = alpha.lock().unwrap();
+                        if a > *alpha {
+                            *alpha = a;
+                        }
+                    }
+                });
+            }
+        });
+        let mut exact = exact.into_inner().unwrap();
+        exact.sort_by_key(|&(i, _)| i);
+        let mut best = exact[0];
+        for &(i, a) in exact[1..].iter() {
+            if a > best.1 {
+                best = (i, a);
+            }
+        }
+        (moves[best.0], best.1)
+    }
+}
@@ -0,0 +1,291 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use super::{Evaluate, DISC};
+use crate::{BitBoard, Position, Side};
+
+/// Squares of each pattern as `(col, row)`, seen from the `a1` corner
+///
+/// Every pattern is evaluated on the 8 symmetric images of the board.
+const PATTERNS: &[(&str, &[(usize, usize)])] = &[
+    (
+        "edge_2x",
+        &[
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (3, 0),
+            (4, 0),
+            (5, 0),
+            (6, 0),
+            (7, 0),
+            (1, 1),
+            (6, 1),
+        ],
+    ),
+    (
+        "corner_3x3",
+        &[
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (0, 1),
+            (1, 1),
+            (2, 1),
+            (0, 2),
+            (1, 2),
+            (2, 2),
+        ],
+    ),
+    (
+        "corner_2x5",
+        &[
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (3, 0),
+            (4, 0),
+            (0, 1),
+            (1, 1),
+            (2, 1),
+            (3, 1),
+            (4, 1),
+        ],
+    ),
+    (
+        "line_2",
+        &[
+            (0, 1),
+            (1, 1),
+            (2, 1),
+            (3, 1),
+            (4, 1),
+            (5, 1),
+            (6, 1),
+            (7, 1),
+        ],
+    ),
+    (
+        "line_3",
+        &[
+            (0, 2),
+            (1, 2),
+            (2, 2),
+            (3, 2),
+            (4, 2),
+            (5, 2),
+            (6, 2),
+            (7, 2),
+        ],
+    ),
+    (
+        "line_4",
+        &[
+            (0, 3),
+            (1, 3),
+            (2, 3),
+            (3, 3),
+            (4, 3),
+            (5, 3),
+            (6, 3),
+            (7, 3),
+        ],
+    ),
+    (
+        "diagonal_8",
+        &[
+            (0, 0),
+            (1, 1),
+            (2, 2),
+            (3, 3),
+            (4, 4),
+            (5, 5),
+            (6, 6),
+            (7, 7),
+        ],
+    ),
+    (
+        "diagonal_7",
+        &[(1, 0), (2, 1), (3, 2), (4, 3), (5, 4), (6, 5), (7, 6)],
+    ),
+    (
+        "diagonal_6",
+        &[(2, 0), (3, 1), (4, 2), (5, 3), (6, 4), (7, 5)],
+    ),
+    ("diagonal_5", &[(3, 0), (4, 1), (5, 2), (6, 3), (7, 4)]),
+    ("diagonal_4", &[(4, 0), (5, 1), (6, 2), (7, 3)]),
+];
+
+const MAGIC: &[u8; 4] = b"RVPW";
+const VERSION: u32 = 1;
+
+/// Pattern based evaluation with weights split by game phase
+///
+/// Each pattern instance is indexed in base 3 (empty, mine, opponent) and
+/// looks up a weight in discs; the sum of the weights plus a bias is the
+/// estimated final disc differential. Phases split the number of discs on
+/// the board evenly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternEvaluator {
+    phases: usize,
+    /// `weights[phase][offsets[pattern] + index]`, the bias is the last one
+    weights: Vec<Vec<f32>>,
+    patterns: Vec<Vec<Position>>,
+    offsets: Vec<usize>,
+}
+
+impl PatternEvaluator {
+    /// All weights zero
+    ///
+    /// # Panics
+    ///
+    /// Panics if `phases` is not in `1..=61`.
+    pub fn new(phases: usize) -> PatternEvaluator {
+        assert!((1..=61).contains(&phases), "phases must be in 1..=61");
+        let patterns = PATTERNS
+            .iter()
+            .map(|(_, squares)| {
+                squares
+                    .iter()
+                    .map(|&(col, row)| BitBoard::position(col, row))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let mut offsets = Vec::with_capacity(patterns.len());
+        let mut size = 0;
+        for squares in patterns.iter() {
+            offsets.push(size);
+            size += 3usize.pow(squares.len() as u32);
+        }
+        PatternEvaluator {
+            phases,
+            weights: vec![vec![0.0; size + 1]; phases],
+            patterns,
+            offsets,
+        }
+    }
+
+    pub fn phases(&self) -> usize {
+        self.phases
+    }
+
+    /// Number of weights in each phase, bias included
+    pub fn weights_per_phase(&self) -> usize {
+        self.weights[0].len()
+    }
+
+    /// Phase of `board`, by the number of discs put since the initial position
+    pub fn phase(&self, board: &BitBoard) -> usize {
+        let (b, w) = board.count();
+        // 局面を編集すると初期配置より石が少ないこともある
+        let put = (b + w).saturating_sub(4) as usize;
+        (put * self.phases / 61).min(self.phases - 1)
+    }
+
+    pub fn weights(&self, phase: usize) -> &[f32] {
+        &self.weights[phase]
+    }
+
+    pub fn weights_mut(&mut self, phase: usize) -> &mut [f32] {
+        &mut self.weights[phase]
+    }
+
+    /// Weight indices used to evaluate `board` for `side`, bias included
+    ///
+    /// The evaluation is the sum of `weights(phase(board))` at these indices,
+    /// so this is also the feature vector for fitting the weights.
+    pub fn features(&self, board: &BitBoard, side: Side) -> Vec<usize> {
+        let mut features = Vec::with_capacity(self.patterns.len() * 8 + 1);
+        for flag in 0..8 {
+            let (mine, opp) = board.symmetry(flag).mine_opp_keys(side);
+            for (squares, &offset) in self.patterns.iter().zip(self.offsets.iter()) {
+                let index = squares.iter().fold(0, |index, &square| {
+                    index * 3
+                        + if mine & square != 0 {
+                            1
+                        } else if opp & square != 0 {
+                            2
+                        } else {
+                            0
+                        }
+                });
+                features.push(offset + index);
+            }
+        }
+        features.push(self.weights_per_phase() - 1);
+        features
+    }
+
+    /// Estimated final disc differential for `side`, in discs
+    pub fn evaluate_discs(&self, board: &BitBoard, side: Side) -> f32 {
+        let weights = &self.weights[self.phase(board)];
+        self.features(board, side).iter().map(|&i| weights[i]).sum()
+    }
+
+    /// Read weights written by [`PatternEvaluator::save`]
+    pub fn load<R: Read>(reader: &mut R) -> io::Result<PatternEvaluator> {
+        fn invalid(message: &str) -> io::Error {
+            io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+        }
+        fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+            let mut buf = [0; 4];
+            reader.read_exact(&mut buf)?;
+            Ok(u32::from_le_bytes(buf))
+        }
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a pattern weight file"));
+        }
+        if read_u32(reader)? != VERSION {
+            return Err(invalid("unsupported weight file version"));
+        }
+        let phases = read_u32(reader)? as usize;
+        if !(1..=61).contains(&phases) {
+            return Err(invalid("invalid number of phases"));
+        }
+        let mut evaluator = PatternEvaluator::new(phases);
+        if read_u32(reader)? as usize != evaluator.weights_per_phase() {
+            return Err(invalid("weight file is for another pattern set"));
+        }
+        for weights in evaluator.weights.iter_mut() {
+            for w in weights.iter_mut() {
+                *w = f32::from_bits(read_u32(reader)?);
+            }
+        }
+        Ok(evaluator)
+    }
+
+    /// Write the weights as little endian `f32`, after a small header
+    pub fn save<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(self.phases as u32).to_le_bytes())?;
+        writer.write_all(&(self.weights_per_phase() as u32).to_le_bytes())?;
+        for weights in self.weights.iter() {
+            for w in weights.iter() {
+                writer.write_all(&w.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn load_file<P: AsRef<Path>>(path: P) -> io::Result<PatternEvaluator> {
+        PatternEvaluator::load(&mut BufReader::new(File::open(path)?))
+    }
+
+    pub fn save_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.save(&mut writer)?;
+        writer.flush()
+    }
+}
+
+impl Evaluate for PatternEvaluator {
+    fn evaluate(&self, board: &BitBoard, side: Side) -> i32 {
+        (self.evaluate_discs(board, side) * DISC as f32).round() as i32
+    }
+}
@@ -1,6 +1,8 @@
+use std::time::Duration;
+
 use rand::Rng;
 
-use super::{search_exact_with_candidates, Occupied, Position, Search};
+use super::{Evaluation, ExactSolver, Occupied, Search, SearchResult};
 use crate::{BitBoard, Candidate, Side};
 
 #[derive(Debug, Clone)]
@@ -18,45 +20,78 @@ impl<R: Rng> Search for RandomSearch<R> {
     fn search(
         &mut self,
         _board: &BitBoard,
+        _side: Side,
         _occupied: Occupied,
         candidates: &mut Candidate,
         _last_passed: bool,
-    ) -> Position {
+    ) -> SearchResult {
         let i = self.rng.gen_range(0..candidates.len());
-        candidates.nth(i).unwrap()
+        candidates.nth(i).unwrap().into()
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct RandomFullSearch<R> {
-    side: Side,
     rng: R,
     full_search_threshold: Occupied,
+    wld_search_threshold: Occupied,
+    solver: ExactSolver,
 }
 
 impl<R> RandomFullSearch<R> {
-    pub const fn new(side: Side, rng: R, full_search_threshold: Occupied) -> RandomFullSearch<R> {
+    pub const fn new(rng: R, full_search_threshold: Occupied) -> RandomFullSearch<R> {
         RandomFullSearch {
-            side,
             rng,
             full_search_threshold,
+            wld_search_threshold: full_search_threshold,
+            solver: ExactSolver::new(0),
         }
     }
+
+    /// Solve win/loss/draw only from `threshold` occupied squares until the
+    /// full search threshold
+    pub const fn with_wld_threshold(mut self, threshold: Occupied) -> RandomFullSearch<R> {
+        self.wld_search_threshold = threshold;
+        self
+    }
+
+    /// Replace the default solver, which has no transposition table
+    pub fn with_solver(mut self, solver: ExactSolver) -> RandomFullSearch<R> {
+        self.solver = solver;
+        self
+    }
 }
 
 impl<R: Rng> Search for RandomFullSearch<R> {
+    fn set_time_limit(&mut self, limit: Duration) {
+        self.solver.set_time_limit(limit);
+    }
+
     fn search(
         &mut self,
         board: &BitBoard,
+        side: Side,
         occupied: Occupied,
         candidates: &mut Candidate,
-        last_passed: bool,
-    ) -> Position {
-        if occupied < self.full_search_threshold {
-            let i = self.rng.gen_range(0..candidates.len());
-            candidates.nth(i).unwrap()
+        _last_passed: bool,
+    ) -> SearchResult {
+        let nodes = self.solver.nodes();
+        let (position, score) = if occupied >= self.full_search_threshold {
+            let (position, score) = self.solver.solve_with_candidates(board, side, candidates);
+            (position, Evaluation::Exact(score))
+        } else if occupied >= self.wld_search_threshold {
+            let (position, wld) = self
+                .solver
+                .solve_wld_with_candidates(board, side, candidates);
+            (position, Evaluation::Wld(wld))
         } else {
-            search_exact_with_candidates(board, self.side, candidates, last_passed).0
+            let i = self.rng.gen_range(0..candidates.len());
+            return candidates.nth(i).unwrap().into();
+        };
+        SearchResult {
+            score: (!self.solver.stopped()).then_some(score),
+            nodes: self.solver.nodes() - nodes,
+            ..SearchResult::new(position)
         }
     }
 }
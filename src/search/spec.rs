@@ -0,0 +1,182 @@
+use std::{error::Error, fmt, io, str::FromStr, sync::Arc};
+
+use rand_pcg::Mcg128Xsl64;
+
+use super::{
+    AlphaBetaSearch, Budget, ExactSolver, Limit, MctsSearch, MoveOrdering, PatternEvaluator,
+    RandomFullSearch, RandomSearch, Search, SimpleEvaluator,
+};
+
+/// Command line description of a searcher, shared by the binaries
+///
+/// | spec                     | searcher                                       |
+/// |--------------------------|------------------------------------------------|
+/// | `random`                 | [`RandomSearch`]                               |
+/// | `full:E` / `full:E:W`    | [`RandomFullSearch`] solving `E` empties, and win/loss/draw from `W` (at least `E`) |
+/// | `alphabeta:D`            | [`AlphaBetaSearch`] with [`SimpleEvaluator`] to depth `D` |
+/// | `pattern:PATH:D`         | [`AlphaBetaSearch`] with weights loaded from `PATH` |
+/// | `mcts:N`                 | [`MctsSearch`] with `N` random playouts per move |
+///
+/// Parsing `pattern` reads the weight file once; every searcher built from
+/// the spec shares the weights. `PATH` may contain `:`.
+///
+/// ```rust
+/// # use reversi::search::SearcherSpec;
+/// let spec: SearcherSpec = "full:10:14".parse().unwrap();
+/// assert_eq!(spec.to_string(), "full:10:14");
+/// assert!("full:x".parse::<SearcherSpec>().is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub enum SearcherSpec {
+    Random,
+    Full {
+        empties: u8,
+        wld_empties: u8,
+    },
+    AlphaBeta {
+        depth: u8,
+    },
+    Pattern {
+        path: String,
+        evaluator: Arc<PatternEvaluator>,
+        depth: u8,
+    },
+    Mcts {
+        playouts: u64,
+    },
+}
+
+impl SearcherSpec {
+    /// A new searcher, whose randomness is derived from `seed`
+    pub fn build(&self, seed: u128) -> Box<dyn Search + Send> {
+        let rng = Mcg128Xsl64::new(seed);
+        match self {
+            SearcherSpec::Random => Box::new(RandomSearch::new(rng)),
+            &SearcherSpec::Full {
+                empties,
+                wld_empties,
+            } => Box::new(
+                RandomFullSearch::new(rng, 64 - empties)
+                    .with_wld_threshold(64 - wld_empties)
+                    .with_solver(ExactSolver::new(1 << 16).with_ordering(MoveOrdering::ALL)),
+            ),
+            &SearcherSpec::AlphaBeta { depth } => {
+                Box::new(AlphaBetaSearch::new(SimpleEvaluator, Limit::Depth(depth)))
+            }
+            SearcherSpec::Pattern {
+                evaluator, depth, ..
+            } => Box::new(AlphaBetaSearch::new(
+                evaluator.clone(),
+                Limit::Depth(*depth),
+            )),
+            &SearcherSpec::Mcts { playouts } => Box::new(MctsSearch::new(
+                RandomSearch::new(rng),
+                Budget::Playouts(playouts),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SearcherSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SearcherSpec::Random => f.write_str("random"),
+            SearcherSpec::Full {
+                empties,
+                wld_empties,
+            } if empties == wld_empties => write!(f, "full:{}", empties),
+            SearcherSpec::Full {
+                empties,
+                wld_empties,
+            } => write!(f, "full:{}:{}", empties, wld_empties),
+            SearcherSpec::AlphaBeta { depth } => write!(f, "alphabeta:{}", depth),
+            SearcherSpec::Pattern { path, depth, .. } => write!(f, "pattern:{}:{}", path, depth),
+            SearcherSpec::Mcts { playouts } => write!(f, "mcts:{}", playouts),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ParseSpecError {
+    UnknownSearcher(String),
+    /// The spec had the wrong number of arguments or an unparsable one
+    InvalidArguments(String),
+    /// The pattern weights could not be loaded
+    Weights(String, io::Error),
+}
+
+impl fmt::Display for ParseSpecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseSpecError::UnknownSearcher(s) => write!(f, "unknown searcher {:?}", s),
+            ParseSpecError::InvalidArguments(s) => write!(f, "invalid arguments in {:?}", s),
+            ParseSpecError::Weights(path, e) => write!(f, "failed to load {}: {}", path, e),
+        }
+    }
+}
+
+impl Error for ParseSpecError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseSpecError::Weights(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for SearcherSpec {
+    type Err = ParseSpecError;
+
+    fn from_str(s: &str) -> Result<SearcherSpec, ParseSpecError> {
+        let invalid = || ParseSpecError::InvalidArguments(s.to_string());
+        let empties = |e: &str| match e.parse() {
+            Ok(e) if e <= 60 => Ok(e),
+            _ => Err(invalid()),
+        };
+        // パスに ':' が含まれてもよいよう、深さは後ろから切り出す
+        if let Some(rest) = s.strip_prefix("pattern:") {
+            let mut fields = rest.rsplitn(2, ':');
+            let depth = fields.next().unwrap().parse().map_err(|_| invalid())?;
+            let path = fields.next().ok_or_else(invalid)?;
+            let evaluator = PatternEvaluator::load_file(path)
+                .map_err(|e| ParseSpecError::Weights(path.to_string(), e))?;
+            return Ok(SearcherSpec::Pattern {
+                path: path.to_string(),
+                evaluator: Arc::new(evaluator),
+                depth,
+            });
+        }
+        let args = s.split(':').collect::<Vec<_>>();
+        match args[..] {
+            ["random"] => Ok(SearcherSpec::Random),
+            ["full", e] => {
+                let empties = empties(e)?;
+                Ok(SearcherSpec::Full {
+                    empties,
+                    wld_empties: empties,
+                })
+            }
+            ["full", e, w] => {
+                let (empties, wld_empties) = (empties(e)?, empties(w)?);
+                // W < E だと勝敗探索が一度も使われない
+                if wld_empties < empties {
+                    return Err(invalid());
+                }
+                Ok(SearcherSpec::Full {
+                    empties,
+                    wld_empties,
+                })
+            }
+            ["alphabeta", d] => Ok(SearcherSpec::AlphaBeta {
+                depth: d.parse().map_err(|_| invalid())?,
+            }),
+            ["mcts", n] => Ok(SearcherSpec::Mcts {
+                playouts: n.parse().map_err(|_| invalid())?,
+            }),
+            ["random", ..] | ["full", ..] | ["alphabeta", ..] | ["pattern", ..] | ["mcts", ..] => {
+                Err(invalid())
+            }
+            _ => Err(ParseSpecError::UnknownSearcher(s.to_string())),
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Nodes between two checks of the clock and of the stop flag
+const POLL_INTERVAL: u64 = 1 << 10;
+
+/// Conditions that cut a search short
+///
+/// Every limit applies to each call of the searcher separately. A searcher
+/// stopped early still answers with the best move it has found so far.
+#[derive(Debug, Clone, Default)]
+pub struct StopCondition {
+    time: Option<Duration>,
+    nodes: Option<u64>,
+    flag: Option<Arc<AtomicBool>>,
+}
+
+impl StopCondition {
+    /// Never stop
+    pub const fn new() -> StopCondition {
+        StopCondition {
+            time: None,
+            nodes: None,
+            flag: None,
+        }
+    }
+
+    /// Stop once `time` has elapsed since the search started
+    pub const fn with_time(mut self, time: Duration) -> StopCondition {
+        self.time = Some(time);
+        self
+    }
+
+    /// Stop after visiting `nodes` nodes
+    pub const fn with_nodes(mut self, nodes: u64) -> StopCondition {
+        self.nodes = Some(nodes);
+        self
+    }
+
+    /// Stop as soon as `flag` is set, typically from another thread
+    pub fn with_flag(mut self, flag: Arc<AtomicBool>) -> StopCondition {
+        self.flag = Some(flag);
+        self
+    }
+
+    pub fn time(&self) -> Option<Duration> {
+        self.time
+    }
+
+    pub fn nodes(&self) -> Option<u64> {
+        self.nodes
+    }
+
+    /// Start watching a search whose node counter is at `nodes`
+    pub(crate) fn start(&self, nodes: u64) -> Stopper {
+        Stopper {
+            deadline: self.time.map(|time| Instant::now() + time),
+            last_node: self.nodes.map(|n| nodes.saturating_add(n)),
+            flag: self.flag.clone(),
+            stopped: false,
+        }
+    }
+}
+
+/// A [`StopCondition`] applied to one search
+#[derive(Debug, Clone)]
+pub(crate) struct Stopper {
+    deadline: Option<Instant>,
+    last_node: Option<u64>,
+    flag: Option<Arc<AtomicBool>>,
+    stopped: bool,
+}
+
+impl Stopper {
+    pub(crate) const fn new() -> Stopper {
+        Stopper {
+            deadline: None,
+            last_node: None,
+            flag: None,
+            stopped: false,
+        }
+    }
+
+    pub(crate) fn stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Cheap check to call at every node, `nodes` being the node counter
+    #[inline]
+    pub(crate) fn check(&mut self, nodes: u64) -> bool {
+        if self.stopped {
+            return true;
+        }
+        if nodes & (POLL_INTERVAL - 1) == 0 {
+            return self.poll(nodes);
+        }
+        if let Some(last) = self.last_node {
+            self.stopped = nodes > last;
+        }
+        self.stopped
+    }
+
+    /// Check every condition now
+    pub(crate) fn poll(&mut self, nodes: u64) -> bool {
+        self.stopped = self.stopped
+            || self.last_node.is_some_and(|last| nodes > last)
+            || self.deadline.is_some_and(|d| Instant::now() >= d)
+            || self
+                .flag
+                .as_ref()
+                .is_some_and(|f| f.load(Ordering::Relaxed));
+        self.stopped
+    }
+}
@@ -0,0 +1,115 @@
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_pcg::Mcg128Xsl64;
+
+use super::{ExactSolver, MoveOrdering, PatternEvaluator, RandomSearch};
+use crate::{notation::Move, BitBoard, Game, Side};
+
+/// A position prepared for fitting a [`PatternEvaluator`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    phase: usize,
+    features: Vec<u32>,
+    label: f32,
+}
+
+impl Sample {
+    /// `board` to move for `side`, whose final disc differential is `label`
+    pub fn new(evaluator: &PatternEvaluator, board: &BitBoard, side: Side, label: f32) -> Sample {
+        Sample {
+            phase: evaluator.phase(board),
+            features: evaluator
+                .features(board, side)
+                .into_iter()
+                .map(|f| f as u32)
+                .collect(),
+            label,
+        }
+    }
+
+    pub fn phase(&self) -> usize {
+        self.phase
+    }
+
+    pub fn label(&self) -> f32 {
+        self.label
+    }
+}
+
+/// Positions with at most `empties` empty squares from `games` [`Game`]s
+/// between two [`RandomSearch`]es, labelled with their exact final disc
+/// differential
+///
+/// Positions where the side to move has to pass are skipped.
+pub fn exact_samples<R: Rng>(
+    evaluator: &PatternEvaluator,
+    games: usize,
+    empties: u8,
+    rng: &mut R,
+) -> Vec<Sample> {
+    let mut solver = ExactSolver::new(1 << 16).with_ordering(MoveOrdering::ALL);
+    let mut samples = Vec::new();
+    for _ in 0..games {
+        let mut game = Game::new(
+            RandomSearch::new(Mcg128Xsl64::from_rng(&mut *rng).unwrap()),
+            RandomSearch::new(Mcg128Xsl64::from_rng(&mut *rng).unwrap()),
+        );
+        game.play_game();
+        // 棋譜をたどり直して、指す前の局面を解く
+        let mut board = BitBoard::new();
+        let mut passed = false;
+        for ply in game.history() {
+            match ply.mv {
+                Move::Put(position) => {
+                    let (b, w) = board.count();
+                    if b + w + empties >= 64 {
+                        let score = solver.solve(&board, ply.side, passed);
+                        let label = score.mine as f32 - score.opp as f32;
+                        samples.push(Sample::new(evaluator, &board, ply.side, label));
+                    }
+                    board.make(ply.side, position, ply.flips);
+                    passed = false;
+                }
+                Move::Pass => passed = true,
+            }
+        }
+    }
+    samples
+}
+
+impl PatternEvaluator {
+    /// Mean squared error of the evaluation against the labels, in discs
+    pub fn loss(&self, samples: &[Sample]) -> f32 {
+        let total: f32 = samples
+            .iter()
+            .map(|sample| {
+                let error = sample.label - self.predict(sample);
+                error * error
+            })
+            .sum();
+        total / samples.len().max(1) as f32
+    }
+
+    /// One pass of stochastic gradient descent on the squared error over
+    /// `samples` shuffled by `rng`, returning the mean squared error seen
+    /// during the pass
+    pub fn fit_epoch<R: Rng>(&mut self, samples: &[Sample], rate: f32, rng: &mut R) -> f32 {
+        let mut order = (0..samples.len()).collect::<Vec<_>>();
+        order.shuffle(rng);
+        let mut total = 0.0;
+        for i in order {
+            let sample = &samples[i];
+            let error = sample.label - self.predict(sample);
+            total += error * error;
+            let weights = self.weights_mut(sample.phase);
+            for &f in sample.features.iter() {
+                weights[f as usize] += rate * error;
+            }
+        }
+        total / samples.len().max(1) as f32
+    }
+
+    fn predict(&self, sample: &Sample) -> f32 {
+        let weights = self.weights(sample.phase);
+        sample.features.iter().map(|&f| weights[f as usize]).sum()
+    }
+}
@@ -4,7 +4,10 @@ use std::{
     path::Path,
 };
 
-use reversi::{BitBoard, Side};
+use rand::Rng;
+use rand_pcg::Mcg128Xsl64;
+
+use reversi::{BitBoard, ParseSetupError, PutError, Setup, Side};
 
 fn replay_scenario<P>(scenario: P, mut board: BitBoard)
 where
@@ -99,3 +102,107 @@ fn replay001_bit() {
 fn replay002_bit() {
     replay_scenario("tests/board_cases/002.txt", BitBoard::default());
 }
+
+#[test]
+fn setup_round_trip() {
+    let setup: Setup = "XXXXXXXXXXXXXXXXXOOOOOO-XXXXOOOXXXXXXOXXXXXXOOXXXXXOXOXXXXXXXXXX O;"
+        .parse()
+        .unwrap();
+    assert_eq!(setup.side, Side::White);
+    assert_eq!(setup.board.count(), (49, 14));
+    assert_eq!(
+        setup.to_string().parse::<Setup>().unwrap(),
+        Setup::new(
+            BitBoard::from_masks(setup.board.black(), setup.board.white()),
+            Side::White
+        )
+    );
+    assert_eq!("--".parse::<Setup>(), Err(ParseSetupError::Length(2)));
+    assert_eq!(
+        format!("{}X", "-".repeat(63)).parse::<Setup>(),
+        Err(ParseSetupError::Length(64))
+    );
+    assert_eq!(
+        format!("{}? X", "-".repeat(63)).parse::<Setup>(),
+        Err(ParseSetupError::InvalidSquare('?'))
+    );
+    assert_eq!(
+        format!("{} Z", "-".repeat(64)).parse::<Setup>(),
+        Err(ParseSetupError::InvalidSide('Z'))
+    );
+}
+
+#[test]
+fn try_put_rejects_illegal_moves() {
+    let mut board = BitBoard::new();
+    let occupied = BitBoard::position(3, 3);
+    let no_flips = BitBoard::position(0, 0);
+    let multi = BitBoard::position(2, 3) | BitBoard::position(3, 2);
+    assert_eq!(
+        board.try_put(Side::Black, occupied),
+        Err(PutError::Occupied(occupied))
+    );
+    assert_eq!(
+        board.try_put(Side::Black, no_flips),
+        Err(PutError::NoFlips(no_flips))
+    );
+    assert_eq!(
+        board.try_put(Side::Black, multi),
+        Err(PutError::NotSingleSquare(multi))
+    );
+    assert_eq!(
+        board.try_put(Side::Black, 0),
+        Err(PutError::NotSingleSquare(0))
+    );
+    assert_eq!(board, BitBoard::new());
+    assert_eq!(
+        board.try_put(Side::Black, BitBoard::position(2, 3)),
+        Ok(BitBoard::position(3, 3))
+    );
+    assert_eq!(board.count(), (4, 1));
+}
+
+#[test]
+fn make_unmake_restores_board() {
+    let mut rng = Mcg128Xsl64::new(1);
+    for _ in 0..100 {
+        let mut board = BitBoard::new();
+        let mut side = Side::Black;
+        loop {
+            let candidates = board.candidates(side).collect::<Vec<_>>();
+            if candidates.is_empty() {
+                side = side.flip();
+                if board.candidates(side).len() == 0 {
+                    break;
+                }
+                continue;
+            }
+            for &pos in candidates.iter() {
+                let before = board.clone();
+                let flips = board.flips(side, pos);
+                assert_ne!(flips, 0);
+                board.make(side, pos, flips);
+                let mut put = before.clone();
+                put.put(side, pos);
+                assert_eq!(board, put);
+                board.unmake(side, pos, flips);
+                assert_eq!(board, before);
+            }
+            let pos = candidates[rng.gen_range(0..candidates.len())];
+            board.put(side, pos);
+            side = side.flip();
+        }
+    }
+}
+
+#[test]
+fn format_marks_candidates() {
+    let mut out = Vec::new();
+    BitBoard::new()
+        .format_candidates(Side::White, &mut out)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "________\n________\n____*___\n___○●*__\n__*●○___\n___*____\n________\n________\n"
+    );
+}
@@ -1,7 +1,16 @@
+use std::{thread, time::Duration};
+
 use rand::SeedableRng;
 use rand_pcg::Mcg128Xsl64;
 
-use reversi::{search::RandomSearch, Game};
+use reversi::{
+    notation::{replay_transcript, Move},
+    search::{
+        search_exact, AlphaBetaSearch, CountTurn, Limit, Occupied, RandomFullSearch, RandomSearch,
+        Search, SearchResult, SimpleEvaluator,
+    },
+    ActionResult, BitBoard, Candidate, Game, PutError, Setup, Side, TimeControl, Undo,
+};
 
 #[test]
 fn smoke_enum2d_bit() {
@@ -21,3 +30,264 @@ fn smoke_enum2d_bit() {
         assert!(b + w <= 64);
     }
 }
+
+struct Corner;
+
+impl Search for Corner {
+    fn search(
+        &mut self,
+        _board: &BitBoard,
+        _side: Side,
+        _occupied: Occupied,
+        _candidates: &mut Candidate,
+        _last_passed: bool,
+    ) -> SearchResult {
+        BitBoard::position(0, 0).into()
+    }
+}
+
+#[test]
+#[should_panic(expected = "illegal move")]
+fn illegal_search_result_panics() {
+    let mut game = Game::new(Corner, Corner);
+    game.play_one_turn();
+}
+
+#[test]
+fn undo_redo_and_jump() {
+    let mut rng = Mcg128Xsl64::new(2);
+    for _ in 0..20 {
+        let mut game = Game::new(
+            RandomSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap()),
+            RandomSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap()),
+        );
+        let mut boards = vec![(game.board().clone(), game.side(), game.last_passed())];
+        let (b, w) = loop {
+            match game.play_one_turn() {
+                ActionResult::GameSet(b, w) => break (b, w),
+                action => {
+                    assert_eq!(game.history().last().unwrap().action(), action);
+                    boards.push((game.board().clone(), game.side(), game.last_passed()));
+                }
+            }
+        };
+        assert_eq!(game.history().len() + 1, boards.len());
+        let (replayed, _) = replay_transcript(&game.transcript()).unwrap();
+        assert_eq!(replayed.count(), (b, w));
+
+        let end = game.history().len();
+        for ply in (0..end).rev() {
+            assert!(game.undo().is_some());
+            let state = (game.board().clone(), game.side(), game.last_passed());
+            assert_eq!(state, boards[ply]);
+        }
+        assert!(game.undo().is_none());
+        assert!(game.jump_to(end / 2));
+        assert_eq!(game.board(), &boards[end / 2].0);
+        assert!(game.redo().is_some());
+        assert_eq!(game.board(), &boards[end / 2 + 1].0);
+        assert!(game.jump_to(end));
+        assert_eq!(game.play_game(), (b, w));
+        assert!(!game.jump_to(end + 1));
+    }
+}
+
+#[test]
+fn new_move_clears_redo() {
+    let mut game = Game::new(
+        RandomSearch::new(Mcg128Xsl64::new(3)),
+        RandomSearch::new(Mcg128Xsl64::new(4)),
+    );
+    for _ in 0..10 {
+        game.play_one_turn();
+    }
+    game.undo();
+    game.undo();
+    game.play_one_turn();
+    assert_eq!(game.history().len(), 9);
+    assert!(game.redo().is_none());
+}
+
+#[test]
+fn play_move_bypasses_searcher() {
+    let mut game = Game::new(Corner, RandomSearch::new(Mcg128Xsl64::new(5)));
+    let f5 = BitBoard::position(5, 4);
+    assert_eq!(
+        game.play_move(BitBoard::position(0, 0)),
+        Err(PutError::NoFlips(BitBoard::position(0, 0)))
+    );
+    assert_eq!(game.play_move(f5), Ok(ActionResult::Put(Side::Black, 5, 4)));
+    game.play_one_turn();
+    assert_eq!(game.side(), Side::Black);
+    assert_eq!(game.history()[0].mv, Move::Put(f5));
+    game.undo();
+    game.undo();
+    assert!(game.redo().is_some());
+}
+
+#[test]
+fn start_from_position() {
+    let mut rng = Mcg128Xsl64::new(5);
+    for _ in 0..10 {
+        let mut game = Game::new(
+            RandomSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap()),
+            RandomSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap()),
+        );
+        while game.board().count().0 + game.board().count().1 < 54 {
+            if let ActionResult::GameSet(_, _) = game.play_one_turn() {
+                break;
+            }
+        }
+        let (board, side, passed) = (game.board().clone(), game.side(), game.last_passed());
+        let expect = search_exact(&board, side, passed);
+        let (b, w) = Game::with_position(
+            board,
+            side,
+            passed,
+            RandomFullSearch::new(Mcg128Xsl64::new(0), 0),
+            RandomFullSearch::new(Mcg128Xsl64::new(0), 0),
+        )
+        .play_game();
+        let actual = CountTurn::with_side(side, b, w, 0);
+        assert_eq!((actual.mine, actual.opp), (expect.mine, expect.opp));
+    }
+}
+
+#[test]
+fn start_after_pass() {
+    // 黒は打てないので、直前に白がパスしていれば即終局
+    let setup: Setup = "OOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOXXXX-- X"
+        .parse()
+        .unwrap();
+    let mut game = Game::with_position(
+        setup.board.clone(),
+        Side::Black,
+        true,
+        RandomSearch::new(Mcg128Xsl64::new(0)),
+        RandomSearch::new(Mcg128Xsl64::new(0)),
+    );
+    assert_eq!(game.play_one_turn(), ActionResult::GameSet(4, 58));
+    let mut game = Game::with_position(
+        setup.board,
+        Side::Black,
+        false,
+        RandomSearch::new(Mcg128Xsl64::new(0)),
+        RandomSearch::new(Mcg128Xsl64::new(0)),
+    );
+    assert_eq!(game.play_one_turn(), ActionResult::Pass(Side::Black));
+    assert!(game.undo().is_some());
+    assert!(!game.last_passed());
+}
+
+struct Slow;
+
+impl Search for Slow {
+    fn search(
+        &mut self,
+        _board: &BitBoard,
+        _side: Side,
+        _occupied: Occupied,
+        candidates: &mut Candidate,
+        _last_passed: bool,
+    ) -> SearchResult {
+        thread::sleep(Duration::from_millis(20));
+        candidates.next().unwrap().into()
+    }
+}
+
+#[test]
+fn running_out_of_time_loses() {
+    let mut game = Game::new(Slow, Slow)
+        .with_time_control(Side::Black, TimeControl::PerMove(Duration::from_millis(5)));
+    assert_eq!(game.play_one_turn(), ActionResult::TimeLoss(Side::Black));
+    assert_eq!(game.play_one_turn(), ActionResult::TimeLoss(Side::Black));
+    assert_eq!(game.time_loss(), Some(Side::Black));
+    assert!(game.history().is_empty());
+    assert_eq!(game.play_game(), (2, 2));
+    // 負けた後は手を受け付けない
+    let f5 = BitBoard::position(5, 4);
+    assert_eq!(game.play_move(f5), Ok(ActionResult::TimeLoss(Side::Black)));
+    assert!(game.history().is_empty());
+    // 時間切れだけを取り消す
+    assert_eq!(game.undo(), Some(Undo::TimeLoss(Side::Black)));
+    assert_eq!(game.time_loss(), None);
+    assert_eq!(game.undo(), None);
+    assert_eq!(game.play_move(f5), Ok(ActionResult::Put(Side::Black, 5, 4)));
+
+    // Slow は必ず 20ms 以上かかるので、何手目で負けるかは幅を持たせて確かめる
+    let total = Duration::from_millis(100);
+    let mut game = Game::new(Slow, Slow).with_time_control(
+        Side::White,
+        TimeControl::Fischer {
+            total,
+            increment: Duration::ZERO,
+        },
+    );
+    game.play_game();
+    assert_eq!(game.time_loss(), Some(Side::White));
+    let plies = game.history().len();
+    assert!(plies % 2 == 1 && (3..=9).contains(&plies), "{}", plies);
+    assert!(game.history()[0].elapsed >= Duration::from_millis(20));
+    assert_eq!(game.clock(Side::White).unwrap().remaining(), Duration::ZERO);
+    let spent = |game: &Game<Slow, Slow>| {
+        game.history()
+            .iter()
+            .filter(|p| p.side == Side::White)
+            .map(|p| p.elapsed)
+            .sum::<Duration>()
+    };
+
+    // 時間切れを取り消すと、指されなかった手の消費も戻る
+    assert!(game.redo().is_none());
+    assert_eq!(game.undo(), Some(Undo::TimeLoss(Side::White)));
+    assert_eq!(game.time_loss(), None);
+    assert_eq!(game.history().len(), plies);
+    let before = total - spent(&game);
+    assert_eq!(game.clock(Side::White).unwrap().remaining(), before);
+    let last = *game.history().last().unwrap();
+    assert_eq!(game.undo(), Some(Undo::Ply(last)));
+    let white = *game.history().last().unwrap();
+    assert_eq!(white.side, Side::White);
+    game.undo();
+    assert_eq!(game.clock(Side::White), white.clock.as_ref());
+    assert!(game.jump_to(0));
+    assert_eq!(game.clock(Side::White).unwrap().remaining(), total);
+    assert!(game.jump_to(plies));
+    assert_eq!(game.clock(Side::White).unwrap().remaining(), before);
+    assert_eq!(game.time_loss(), None);
+
+    // 手数を指定して戻るときも時間切れを取り消す
+    game.play_game();
+    assert_eq!(game.time_loss(), Some(Side::White));
+    assert!(game.jump_to(plies - 1));
+    assert_eq!(game.time_loss(), None);
+}
+
+#[test]
+fn searcher_keeps_within_its_clock() {
+    // 負荷の高い環境でも時間切れにならないよう余裕を持たせる
+    let total = Duration::from_millis(1000);
+    let increment = Duration::from_millis(50);
+    let mut game = Game::new(
+        AlphaBetaSearch::new(SimpleEvaluator, Limit::Depth(60)),
+        RandomSearch::new(Mcg128Xsl64::new(3)),
+    )
+    .with_time_control(Side::Black, TimeControl::Fischer { total, increment });
+    game.play_game();
+    assert_eq!(game.time_loss(), None);
+    let black = game
+        .history()
+        .iter()
+        .filter(|p| p.side == Side::Black && p.mv != Move::Pass)
+        .collect::<Vec<_>>();
+    let spent = black.iter().map(|p| p.elapsed).sum::<Duration>();
+    let remaining = game.clock(Side::Black).unwrap().remaining();
+    assert_eq!(remaining, total + increment * black.len() as u32 - spent);
+    for pair in black.windows(2) {
+        let before = pair[0].clock.unwrap().remaining();
+        assert_eq!(
+            pair[1].clock.unwrap().remaining(),
+            before + increment - pair[0].elapsed
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use rand_pcg::Mcg128Xsl64;
+
+use reversi::{
+    nboard::{Engine, EngineError, ExternalEngine, Ggf, GgfError},
+    notation::{replay_transcript, Move},
+    search::{RandomSearch, Search},
+    BitBoard, Game, Side,
+};
+
+const START: &str = "BO[8 ---------------------------O*------*O--------------------------- *]";
+
+/// Run the engine binary on `script` and return its stdout lines
+fn session(script: &str) -> Vec<String> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_nboard"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(script.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+#[test]
+fn parse_ggf_games() {
+    let game: Ggf = format!("(;GM[Othello]PB[x]PW[y]{}B[f5/0.5/1]W[D6]B[pa];)", START)
+        .parse()
+        .unwrap();
+    assert_eq!(game.setup.side, Side::Black);
+    assert_eq!(game.moves.len(), 3);
+    assert_eq!(game.moves[2], Move::Pass);
+    // 黒のパスは不正なので局面は作れない
+    assert!(game.position().is_err());
+
+    let game: Ggf = format!("(;GM[Othello]{}B[F5]W[D6]B[C3];)", START)
+        .parse()
+        .unwrap();
+    assert_eq!(
+        game.position().unwrap(),
+        replay_transcript("f5d6c3").unwrap()
+    );
+
+    assert_eq!(
+        "(;GM[Othello];)".parse::<Ggf>(),
+        Err(GgfError::MissingBoard)
+    );
+    assert_eq!(
+        format!("(;{}B[Z9];)", START).parse::<Ggf>(),
+        Err(GgfError::InvalidMove("Z9".to_string()))
+    );
+    assert_eq!(
+        format!("(;{}B[F5", START).parse::<Ggf>(),
+        Err(GgfError::Unterminated)
+    );
+}
+
+#[test]
+fn engine_keeps_position() {
+    let mut engine = Engine::new();
+    let mut out = Vec::new();
+    assert!(engine.handle("move F5", &mut out).unwrap());
+    assert!(engine.handle("move d6/0.5/0.1", &mut out).unwrap());
+    assert!(engine.handle("move A1", &mut out).is_err());
+    assert_eq!(
+        (engine.board().clone(), engine.side()),
+        replay_transcript("f5d6").unwrap()
+    );
+    assert!(!engine.handle("quit", &mut out).unwrap());
+    assert!(out.is_empty());
+}
+
+#[test]
+fn scripted_session() {
+    let script = format!(
+        "nboard 2\nset depth 2\nset game (;GM[Othello]{}B[F5]W[D6];)\nping 1\nhint 2\ngo\n\
+         move C3\nbogus\nping 2\nquit\nping 3\n",
+        START
+    );
+    let lines = session(&script);
+    assert_eq!(lines[0], "set myname reversi");
+    assert_eq!(lines[1], "pong 1");
+    assert!(lines[2].starts_with("search "));
+    assert!(lines[3].starts_with("search "));
+    assert!(lines[2].ends_with(" 0 2"));
+    assert_eq!(lines[4], "status");
+    // go は最善手と評価値、時間を返すが局面は進めない
+    let best = lines[5].strip_prefix("=== ").unwrap();
+    let hint = lines[2].split_whitespace().nth(1).unwrap();
+    assert!(best.starts_with(hint));
+    assert_eq!(best.split('/').count(), 3);
+    assert_eq!(lines[6], "pong 2");
+    assert_eq!(lines.len(), 7);
+}
+
+#[test]
+fn engine_passes_without_moves() {
+    let lines = session(
+        "set game (;GM[Othello]BO[8 \
+         OOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOOXXXX-- *];)\ngo\n",
+    );
+    assert_eq!(lines, vec!["=== PA/0.00/0.000"]);
+}
+
+/// Start a shell script as an engine
+fn script_engine(script: &str) -> Result<ExternalEngine, EngineError> {
+    ExternalEngine::spawn(
+        Command::new("sh").arg("-c").arg(script),
+        Duration::from_millis(300),
+    )
+}
+
+#[test]
+fn external_engine_plays_a_game() {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_nboard"));
+    command.args(["--exact", "8"]).stderr(Stdio::null());
+    let mut engine = ExternalEngine::spawn(&mut command, Duration::from_secs(10)).unwrap();
+    assert_eq!(engine.name(), Some("reversi"));
+    engine.set_depth(1).unwrap();
+    let mut game = Game::new(engine, RandomSearch::new(Mcg128Xsl64::new(1)));
+    let (b, w) = game.play_game();
+    assert!(b + w <= 64);
+    assert!(game.history().len() >= 60);
+}
+
+#[test]
+fn external_engine_reports_failures() {
+    assert!(matches!(
+        ExternalEngine::spawn(
+            &mut Command::new("/nonexistent/engine"),
+            Duration::from_secs(1)
+        ),
+        Err(EngineError::Spawn(_))
+    ));
+    assert!(matches!(
+        script_engine("cat >/dev/null"),
+        Err(EngineError::Timeout { .. })
+    ));
+    assert!(matches!(script_engine("exit 0"), Err(EngineError::Exited)));
+
+    // ping には答えるが合法でない手を返すエンジン
+    let mut engine = script_engine(
+        "while read cmd arg; do case $cmd in \
+         ping) echo pong $arg;; go) echo '=== A1/1.0';; esac; done",
+    )
+    .unwrap();
+    assert!(matches!(
+        engine.try_search(&BitBoard::new(), Side::Black),
+        Err(EngineError::IllegalMove(mv)) if mv == "A1"
+    ));
+
+    // go に答えないエンジン
+    let mut engine =
+        script_engine("while read cmd arg; do [ $cmd = ping ] && echo pong $arg; done").unwrap();
+    assert!(matches!(
+        engine.try_search(&BitBoard::new(), Side::Black),
+        Err(EngineError::Timeout { command }) if command == "go"
+    ));
+}
+
+#[test]
+#[should_panic(expected = "illegal move")]
+fn external_engine_keeps_to_the_candidates() {
+    // f5 は合法だが、候補手として渡すのは最後の e6 だけ
+    let mut engine = script_engine(
+        "while read cmd arg; do case $cmd in \
+         ping) echo pong $arg;; go) echo '=== F5/1.0';; esac; done",
+    )
+    .unwrap();
+    let board = BitBoard::new();
+    let mut candidates = board.candidates(Side::Black);
+    while candidates.len() > 1 {
+        candidates.next();
+    }
+    assert!(candidates.contains(BitBoard::position(4, 5)));
+    engine.search(&board, Side::Black, 4, &mut candidates, false);
+}
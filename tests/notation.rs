@@ -0,0 +1,96 @@
+use rand::SeedableRng;
+use rand_pcg::Mcg128Xsl64;
+
+use reversi::{
+    notation::{
+        format_transcript, parse_square, parse_transcript, replay, replay_plies, replay_transcript,
+        square_name, Move, NotationError,
+    },
+    search::RandomSearch,
+    ActionResult, BitBoard, Game, Side,
+};
+
+#[test]
+fn square_names_round_trip() {
+    for row in 0..8 {
+        for col in 0..8 {
+            let position = BitBoard::position(col, row);
+            assert_eq!(parse_square(&square_name(position)), Some(position));
+        }
+    }
+    assert_eq!(parse_square("a0"), None);
+    assert_eq!(parse_square("h9"), None);
+    assert_eq!(parse_square("f"), None);
+}
+
+#[test]
+fn transcript_round_trip() {
+    let moves = parse_transcript("F5 d6 c3 pa").unwrap();
+    assert_eq!(moves.len(), 4);
+    assert_eq!(moves[3], Move::Pass);
+    assert_eq!(format_transcript(&moves), "f5d6c3pa");
+    assert_eq!(
+        parse_transcript("f5d6x3"),
+        Err(NotationError::InvalidToken {
+            index: 2,
+            token: "x3".to_string()
+        })
+    );
+}
+
+#[test]
+fn illegal_move_index() {
+    // d6 は白の手なので、黒が続けて d6 に打つのは不正
+    assert_eq!(
+        replay_transcript("f5f4d6"),
+        Err(NotationError::IllegalMove {
+            index: 2,
+            side: Side::Black,
+            mv: Move::Put(parse_square("d6").unwrap()),
+        })
+    );
+    assert!(replay_transcript("f5pa").is_err());
+}
+
+#[test]
+fn replay_random_games_with_implicit_passes() {
+    let mut rng = Mcg128Xsl64::new(1);
+    for _ in 0..100 {
+        let mut game = Game::new(
+            RandomSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap()),
+            RandomSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap()),
+        );
+        let mut board = BitBoard::new();
+        let mut moves = Vec::new();
+        loop {
+            match game.play_one_turn() {
+                ActionResult::GameSet(b, w) => {
+                    assert_eq!(board.count(), (b, w));
+                    break;
+                }
+                ActionResult::Pass(_) => (),
+                ActionResult::TimeLoss(_) => unreachable!(),
+                ActionResult::Put(side, col, row) => {
+                    let position = BitBoard::position(col, row);
+                    board.put(side, position);
+                    moves.push(Move::Put(position));
+                }
+            }
+        }
+        let transcript = format_transcript(&moves);
+        let (replayed, _) = replay_transcript(&transcript).unwrap();
+        assert_eq!(replayed, board);
+        let (replayed, _) = replay(&BitBoard::new(), Side::Black, &moves).unwrap();
+        assert_eq!(replayed, board);
+
+        // 省いたパスも含めて対局の記録と同じ手順になる
+        let (replayed, _, plies) = replay_plies(&BitBoard::new(), Side::Black, &moves).unwrap();
+        assert_eq!(replayed, board);
+        let key = |p: &reversi::Ply| (p.side, p.mv, p.flips);
+        let mut history = game.history().iter().map(key).collect::<Vec<_>>();
+        while history.last().is_some_and(|&(_, mv, _)| mv == Move::Pass) {
+            history.pop();
+        }
+        assert_eq!(plies.iter().map(key).collect::<Vec<_>>(), history);
+    }
+}
@@ -0,0 +1,51 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Run the play binary with `args` on `script` and return its stdout
+fn session(args: &[&str], script: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_play"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(script.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn human_plays_undoes_and_asks_for_hints() {
+    let out = session(
+        &["--hint", "alphabeta:2", "alphabeta:2"],
+        "undo\nf5\ne9\nd3\nhint\nundo\nquit\n",
+    );
+    let lines = out.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "you play black against alphabeta:2");
+    assert!(out.contains("nothing to undo"));
+    assert!(out.contains("black plays f5\nwhite plays "));
+    assert!(out.contains("unknown command: e9"));
+    assert!(out.contains("d3 is not a legal move"));
+    assert!(out.contains("hint: "));
+    // undo で黒の最初の手番に戻り、候補手が表示される
+    let last = out.rsplit("black> \n").next().unwrap();
+    assert!(last.starts_with(
+        "  a b c d e f g h\n1 _ _ _ _ _ _ _ _\n2 _ _ _ _ _ _ _ _\n3 _ _ _ * _ _ _ _\n"
+    ));
+    assert!(last.contains("● 2 ○ 2"));
+}
+
+#[test]
+fn engine_moves_first_for_white() {
+    let out = session(&["--side", "white", "random"], "quit\n");
+    assert!(out.contains("black plays "));
+    assert!(out.ends_with("white> "));
+}
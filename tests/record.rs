@@ -0,0 +1,44 @@
+use reversi::{elo, search::Wld, Record};
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 0.05
+}
+
+#[test]
+fn elo_of_known_scores() {
+    assert!(close(elo(0.5), 0.0));
+    assert!(close(elo(10.0 / 11.0), 400.0));
+    assert!(close(elo(1.0 / 11.0), -400.0));
+    assert!(close(elo(0.75), 190.85));
+    assert!(close(elo(0.64), 99.96));
+    // 全勝・全敗は無限大にせず頭打ちにする
+    assert!(close(elo(1.0), 1199.83));
+    assert!(close(elo(0.0), -1199.83));
+    assert_eq!(elo(1.0), elo(2.0));
+}
+
+#[test]
+fn record_counts_and_intervals() {
+    let mut record = Record::default();
+    for &wld in [Wld::Win, Wld::Win, Wld::Draw, Wld::Loss].iter() {
+        record.add(wld);
+    }
+    assert_eq!(record, Record::new(2, 1, 1));
+    assert_eq!(record.games(), 4);
+    assert!(close(record.score(), 0.625));
+    let (e, low, high) = record.elo();
+    assert!(close(e, elo(0.625)));
+    assert!(low < e && e < high);
+
+    record.merge(Record::new(0, 0, 4));
+    assert_eq!(record, Record::new(2, 1, 5));
+
+    let (e, low, high) = Record::new(10, 0, 0).elo();
+    assert!(close(e, 1199.83));
+    assert_eq!((low, high), (e, e));
+    let (e, low, high) = Record::new(0, 0, 10).elo();
+    assert!(close(e, -1199.83));
+    assert_eq!((low, high), (e, e));
+    let (e, low, high) = Record::new(0, 6, 0).elo();
+    assert_eq!((e, low, high), (0.0, 0.0, 0.0));
+}
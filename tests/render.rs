@@ -0,0 +1,194 @@
+use rand_pcg::Mcg128Xsl64;
+
+use reversi::{
+    notation::{replay_transcript, Move},
+    render::{Render, Svg, SvgAnimation},
+    search::RandomSearch,
+    BitBoard, Game, Ply, Side,
+};
+
+#[test]
+fn render_plain_grid() {
+    let (mut board, side) = replay_transcript("f5").unwrap();
+    let d6 = BitBoard::position(3, 5);
+    let flips = board.legal_flips(side, d6).unwrap();
+    board.make(side, d6, flips);
+    let ply = Ply {
+        side,
+        mv: Move::Put(d6),
+        flips,
+        elapsed: Default::default(),
+        clock: None,
+    };
+    let render = Render::new(&board)
+        .with_candidates(side.flip())
+        .with_last_move(&ply);
+    assert_eq!(
+        render.to_string(),
+        "  a b c d e f g h
+1 _ _ _ _ _ _ _ _
+2 _ _ _ _ _ _ _ _
+3 _ _ * _ _ _ _ _
+4 _ _ * ○ ● _ _ _
+5 _ _ * ○ ● ● _ _
+6 _ _ *[○]_ _ _ _
+7 _ _ * _ _ _ _ _
+8 _ _ _ _ _ _ _ _
+"
+    );
+    assert_eq!(
+        render.clone().with_labels(false).to_string().lines().nth(5),
+        Some("_ _ *[○]_ _ _ _")
+    );
+    // 1 行に並べる形式では幅を変えずに記号で示す
+    let compact = render.with_compact(true).to_string();
+    assert_eq!(
+        compact,
+        "________/________/__*_____/__*○●___/__*○●●__/__*◇____/__*_____/________"
+    );
+    assert!(compact.split('/').all(|row| row.chars().count() == 8));
+
+    // 端のマスでも括弧が崩れない
+    let pass = Ply {
+        mv: Move::Pass,
+        ..ply
+    };
+    let board = BitBoard::from_masks(0, BitBoard::position(7, 0));
+    let h1 = Ply {
+        mv: Move::Put(BitBoard::position(7, 0)),
+        flips: 0,
+        ..ply
+    };
+    let render = Render::new(&board).with_labels(false).with_last_move(&h1);
+    assert_eq!(render.to_string().lines().next(), Some("_ _ _ _ _ _ _[○]"));
+    let black = BitBoard::from_masks(BitBoard::position(7, 0), 0);
+    let h1 = Ply {
+        side: Side::Black,
+        ..h1
+    };
+    assert!(Render::new(&black)
+        .with_compact(true)
+        .with_last_move(&h1)
+        .to_string()
+        .starts_with("_______◆/"));
+    let render = render.with_last_move(&pass);
+    assert_eq!(render.to_string().lines().next(), Some("_ _ _ _ _ _ _ ○"));
+}
+
+#[test]
+fn render_colour_highlights() {
+    let (mut board, side) = replay_transcript("f5").unwrap();
+    let d6 = BitBoard::position(3, 5);
+    let flips = board.legal_flips(side, d6).unwrap();
+    board.make(side, d6, flips);
+    let ply = Ply {
+        side,
+        mv: Move::Put(d6),
+        flips,
+        elapsed: Default::default(),
+        clock: None,
+    };
+    let s = Render::new(&board)
+        .with_colour(true)
+        .with_last_move(&ply)
+        .to_string();
+    assert_eq!(s.lines().count(), 9);
+    assert!(s.lines().skip(1).all(|l| l.ends_with("\x1b[0m")));
+    // 最後の手と返した石は背景色が変わり、括弧は付かない
+    assert_eq!(s.matches("\x1b[43;97m●").count(), 1);
+    assert_eq!(
+        s.matches("\x1b[46;97m●").count(),
+        flips.count_ones() as usize
+    );
+    assert!(!s.contains(']'));
+}
+
+#[test]
+fn board_debug_output() {
+    let board = BitBoard::new();
+    assert_eq!(
+        format!("{:?}", board),
+        "BitBoard(________/________/________/___○●___/___●○___/________/________/________)"
+    );
+    let pretty = format!("{:#?}", board);
+    assert!(pretty.starts_with("BitBoard\n  a b c d e f g h\n1 _ _ _ _ _ _ _ _\n"));
+    assert_eq!(pretty.lines().count(), 10);
+}
+
+#[test]
+fn svg_diagram() {
+    let mut game = Game::new(
+        RandomSearch::new(Mcg128Xsl64::new(1)),
+        RandomSearch::new(Mcg128Xsl64::new(2)),
+    );
+    for _ in 0..6 {
+        game.play_one_turn();
+    }
+    let board = game.board();
+    let discs = board.count();
+    let svg = Svg::new(board)
+        .with_candidates(game.side())
+        .with_move_numbers(game.history())
+        .with_annotation(BitBoard::position(0, 0), "<+3>")
+        .to_string();
+    assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="368""#));
+    assert!(svg.ends_with("</svg>\n"));
+    let discs = (discs.0 + discs.1) as usize;
+    let candidates = board.candidates(game.side()).len();
+    // 石、候補手、星の4点
+    assert_eq!(svg.matches("<circle").count(), discs + candidates + 4);
+    assert_eq!(
+        svg.contains(r#"fill="black" fill-opacity="0.4""#),
+        game.side() == Side::Black
+    );
+    // 座標 16 個、手数 6 個、注釈 1 個
+    assert_eq!(svg.matches("<text").count(), 16 + 6 + 1);
+    assert!(svg.contains(">6</text>"));
+    assert!(svg.contains(">&lt;+3&gt;</text>"));
+
+    let plain = Svg::new(board)
+        .with_coordinates(false)
+        .with_square_size(10.0)
+        .with_last_move(game.history().last().unwrap())
+        .to_string();
+    assert!(plain.contains(r#"width="80""#));
+    assert!(!plain.contains("<text"));
+    assert_eq!(plain.matches("<circle").count(), discs + 4 + 1);
+}
+
+#[test]
+fn svg_animation() {
+    let mut game = Game::new(
+        RandomSearch::new(Mcg128Xsl64::new(3)),
+        RandomSearch::new(Mcg128Xsl64::new(4)),
+    );
+    game.play_game();
+    let animation = SvgAnimation::new(game.board(), game.history())
+        .with_frame_duration(std::time::Duration::from_millis(500));
+    let n = game.history().len() + 1;
+    assert_eq!(animation.frames(), n);
+    let svg = animation.to_string();
+    assert_eq!(svg.matches("<g ").count(), n);
+    assert_eq!(svg.matches("<animate ").count(), n);
+    assert!(svg.contains(&format!(r#"dur="{}s""#, n as f64 * 0.5)));
+    // 最初のフレームは初期配置
+    let first = svg.split("<g ").nth(1).unwrap();
+    assert!(first.starts_with(r#"visibility="visible">"#));
+    assert_eq!(first.matches("<circle").count(), 4);
+}
+
+#[test]
+#[should_panic(expected = "history does not lead to the board")]
+fn svg_animation_checks_history() {
+    let (board, _) = replay_transcript("f5").unwrap();
+    let (other, side) = replay_transcript("d3").unwrap();
+    let c3 = BitBoard::position(2, 2);
+    let ply = Ply {
+        side,
+        mv: Move::Put(c3),
+        flips: other.flips(side, c3),
+        elapsed: Default::default(),
+        clock: None,
+    };
+    SvgAnimation::new(&board, &[ply]);
+}
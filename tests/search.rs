@@ -0,0 +1,593 @@
+use std::{
+    sync::{atomic::AtomicBool, Arc},
+    time::{Duration, Instant},
+};
+
+use rand::SeedableRng;
+use rand_pcg::Mcg128Xsl64;
+
+use reversi::{
+    notation::{format_transcript, parse_transcript, replay, Move},
+    search::{
+        exact_samples, search_exact, search_exact_pv, search_exact_with_candidates, search_wld,
+        AlphaBetaSearch, Budget, CountTurn, Estimate, Evaluation, ExactSolver, Limit, MctsSearch,
+        MoveOrdering, ParallelSolver, PatternEvaluator, RandomFullSearch, RandomSearch, Score,
+        Search, SearcherSpec, SimpleEvaluator, StopCondition, Wld, DISC,
+    },
+    ActionResult, BitBoard, Game, Side,
+};
+
+/// Play random games until `empties` squares are left
+fn random_positions(seed: u128, n: usize, empties: u8) -> Vec<(BitBoard, Side, bool)> {
+    let mut rng = Mcg128Xsl64::new(seed);
+    let mut positions = Vec::with_capacity(n);
+    while positions.len() < n {
+        let mut game = Game::new(
+            RandomSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap()),
+            RandomSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap()),
+        );
+        loop {
+            let (b, w) = game.board().count();
+            if b + w + empties >= 64 {
+                positions.push((game.board().clone(), game.side(), game.last_passed()));
+                break;
+            }
+            if let ActionResult::GameSet(_, _) = game.play_one_turn() {
+                break;
+            }
+        }
+    }
+    positions
+}
+
+#[test]
+fn full_search_reports_exact_score() {
+    for (board, side, passed) in random_positions(1, 20, 8) {
+        let mut candidates = board.candidates(side);
+        if candidates.len() == 0 {
+            continue;
+        }
+        let (b, w) = board.count();
+        let result = RandomFullSearch::new(Mcg128Xsl64::new(0), 0).search(
+            &board,
+            side,
+            b + w,
+            &mut candidates,
+            passed,
+        );
+        assert!(board.candidates(side).contains(result.position));
+        assert_eq!(
+            result.score,
+            Some(Evaluation::Exact(search_exact(&board, side, passed)))
+        );
+    }
+}
+
+fn minimax(board: &BitBoard, side: Side, passed: bool) -> i32 {
+    let candidates = board.candidates(side);
+    if candidates.len() == 0 {
+        return if passed {
+            let (mine, opp) = board.mine_opp_keys(side);
+            mine.count_ones() as i32 - opp.count_ones() as i32
+        } else {
+            -minimax(board, side.flip(), true)
+        };
+    }
+    candidates
+        .map(|pos| {
+            let mut board = board.clone();
+            board.put(side, pos);
+            -minimax(&board, side.flip(), false)
+        })
+        .max()
+        .unwrap()
+}
+
+#[test]
+fn alpha_beta_is_exact_at_full_depth() {
+    for (board, side, passed) in random_positions(2, 20, 7) {
+        let mut candidates = board.candidates(side);
+        if candidates.len() == 0 {
+            continue;
+        }
+        let (b, w) = board.count();
+        let result = AlphaBetaSearch::new(SimpleEvaluator, Limit::Depth(60)).search(
+            &board,
+            side,
+            b + w,
+            &mut candidates,
+            passed,
+        );
+        let expect = minimax(&board, side, passed);
+        assert_eq!(
+            result.score,
+            Some(Evaluation::Estimate(Estimate(expect * DISC)))
+        );
+        let pv = result.pv.unwrap();
+        assert_eq!(pv[0], Move::Put(result.position));
+        let (end, last) = replay(&board, side, &pv).unwrap();
+        assert_eq!(end.candidates(last).len(), 0);
+        assert_eq!(end.candidates(last.flip()).len(), 0);
+        let mut after = board.clone();
+        after.put(side, result.position);
+        assert_eq!(-minimax(&after, side.flip(), false), expect);
+    }
+}
+
+#[test]
+fn alpha_beta_respects_node_budget() {
+    for (board, side, passed) in random_positions(3, 10, 40) {
+        let mut candidates = board.candidates(side);
+        if candidates.len() == 0 {
+            continue;
+        }
+        let (b, w) = board.count();
+        let result = AlphaBetaSearch::new(SimpleEvaluator, Limit::Nodes(2000)).search(
+            &board,
+            side,
+            b + w,
+            &mut candidates,
+            passed,
+        );
+        assert!(board.candidates(side).contains(result.position));
+        assert!(result.nodes <= 2001);
+    }
+}
+
+#[test]
+fn alpha_beta_beats_random() {
+    let mut rng = Mcg128Xsl64::new(4);
+    let mut wins = 0;
+    for _ in 0..10 {
+        let (b, w) = Game::new(
+            AlphaBetaSearch::new(SimpleEvaluator, Limit::Depth(3)),
+            RandomSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap()),
+        )
+        .play_game();
+        if b > w {
+            wins += 1;
+        }
+    }
+    assert!(wins >= 8, "{} wins", wins);
+}
+
+#[test]
+fn transposition_table_keeps_exact_scores() {
+    let mut plain = ExactSolver::new(0);
+    let mut table = ExactSolver::new(1 << 12);
+    let mut symmetric = ExactSolver::new(1 << 12).with_symmetry(true);
+    for (board, side, passed) in random_positions(6, 20, 10) {
+        let expect = plain.solve(&board, side, passed);
+        assert_eq!(table.solve(&board, side, passed), expect);
+        assert_eq!(symmetric.solve(&board, side, passed), expect);
+    }
+    assert!(table.nodes() < plain.nodes());
+    for (board, side, _) in random_positions(7, 20, 10) {
+        let mut candidates = board.candidates(side);
+        if candidates.len() > 0 {
+            let expect = search_exact_with_candidates(&board, side, &mut board.candidates(side));
+            let (pos, score) = table.solve_with_candidates(&board, side, &mut candidates);
+            assert!(board.candidates(side).contains(pos));
+            assert_eq!(score, expect.1);
+        }
+    }
+}
+
+#[test]
+fn move_ordering_keeps_exact_scores() {
+    let orderings = [
+        MoveOrdering::NONE,
+        MoveOrdering {
+            fastest_first: true,
+            ..MoveOrdering::NONE
+        },
+        MoveOrdering {
+            corners: true,
+            parity: true,
+            ..MoveOrdering::NONE
+        },
+        MoveOrdering::ALL,
+    ];
+    let positions = random_positions(8, 10, 10);
+    let mut nodes = Vec::new();
+    for &ordering in orderings.iter() {
+        let mut solver = ExactSolver::new(1 << 12).with_ordering(ordering);
+        for (board, side, passed) in positions.iter() {
+            assert_eq!(
+                solver.solve(board, *side, *passed),
+                search_exact(board, *side, *passed)
+            );
+        }
+        nodes.push(solver.nodes());
+    }
+    assert!(nodes[3] < nodes[0]);
+}
+
+#[test]
+fn parallel_solver_matches_sequential() {
+    let mut sequential = ExactSolver::new(1 << 12).with_ordering(MoveOrdering::ALL);
+    for &threads in [1, 4].iter() {
+        let mut parallel = ParallelSolver::new(threads, ExactSolver::new(1 << 12));
+        assert_eq!(parallel.threads(), threads);
+        for (board, side, passed) in random_positions(9, 20, 10) {
+            let expect = sequential.solve(&board, side, passed);
+            assert_eq!(parallel.solve(&board, side, passed), expect);
+            let mut candidates = board.candidates(side);
+            if candidates.len() > 0 {
+                let (pos, score) = parallel.solve_with_candidates(&board, side, &mut candidates);
+                assert_eq!(score, expect);
+                let mut after = board.clone();
+                after.put(side, pos);
+                let chosen = search_exact(&after, side.flip(), false).flip();
+                assert_eq!((chosen.mine, chosen.opp), (score.mine, score.opp));
+                assert!(board.candidates(side).contains(pos));
+            }
+        }
+        assert!(parallel.nodes() > 0);
+    }
+}
+
+#[test]
+fn wld_matches_exact_outcome() {
+    let mut solver = ExactSolver::new(0).with_ordering(MoveOrdering::ALL);
+    for (board, side, passed) in random_positions(10, 30, 10) {
+        let exact = search_exact(&board, side, passed);
+        let wld = Wld::from(exact);
+        assert_eq!(solver.solve_wld(&board, side, passed), Some(wld));
+        assert_eq!(search_wld(&board, side, passed), wld);
+        let mut candidates = board.candidates(side);
+        if candidates.len() == 0 {
+            continue;
+        }
+        let (b, w) = board.count();
+        let result = RandomFullSearch::new(Mcg128Xsl64::new(0), 64)
+            .with_wld_threshold(0)
+            .search(&board, side, b + w, &mut candidates, passed);
+        assert_eq!(result.score, Some(Evaluation::Wld(wld)));
+        let mut after = board.clone();
+        after.put(side, result.position);
+        assert_eq!(search_wld(&after, side.flip(), false).flip(), wld);
+    }
+}
+
+#[test]
+fn wld_search_uses_fewer_nodes_than_exact() {
+    let positions = random_positions(12, 10, 12);
+    let mut exact = ExactSolver::new(1 << 14).with_ordering(MoveOrdering::ALL);
+    let mut wld = ExactSolver::new(1 << 14).with_ordering(MoveOrdering::ALL);
+    let mut symmetric = ExactSolver::new(1 << 14)
+        .with_ordering(MoveOrdering::ALL)
+        .with_symmetry(true);
+    for (board, side, passed) in positions.iter() {
+        let expect = Some(Wld::from(exact.solve(board, *side, *passed)));
+        assert_eq!(wld.solve_wld(board, *side, *passed), expect);
+        assert_eq!(symmetric.solve_wld(board, *side, *passed), expect);
+        // 石数の表が埋まっていても結果は変わらない
+        assert_eq!(exact.solve_wld(board, *side, *passed), expect);
+    }
+    assert!(
+        wld.nodes() * 2 < exact.nodes(),
+        "{} wld nodes, {} exact nodes",
+        wld.nodes(),
+        exact.nodes()
+    );
+}
+
+#[test]
+fn exact_pv_reaches_solved_score() {
+    for (board, side, passed) in random_positions(11, 20, 10) {
+        let (score, pv) = search_exact_pv(&board, side, passed);
+        assert_eq!(score, search_exact(&board, side, passed));
+        let moves = parse_transcript(&format_transcript(&pv)).unwrap();
+        assert_eq!(moves, pv);
+        let (end, _) = replay(&board, side, &pv).unwrap();
+        assert_eq!(end.candidates(Side::Black).len(), 0);
+        assert_eq!(end.candidates(Side::White).len(), 0);
+        let (b, w) = end.count();
+        let actual = CountTurn::with_side(side, b, w, 0);
+        assert_eq!((actual.mine, actual.opp), (score.mine, score.opp));
+        let puts = pv.iter().filter(|&&mv| mv != Move::Pass).count();
+        assert_eq!(puts, score.turn as usize);
+    }
+}
+
+#[test]
+fn analyze_scores_every_move() {
+    let mut solver = ExactSolver::new(1 << 12).with_ordering(MoveOrdering::ALL);
+    let mut alpha_beta = AlphaBetaSearch::new(SimpleEvaluator, Limit::Depth(60));
+    for (board, side, passed) in random_positions(12, 20, 7) {
+        let candidates = board.candidates(side).len();
+        if candidates == 0 {
+            continue;
+        }
+        let exact = solver.analyze(&board, side);
+        assert_eq!(exact.len(), candidates);
+        assert_eq!(exact[0].1, search_exact(&board, side, passed));
+        assert!(exact.windows(2).all(|w| w[0].1 >= w[1].1));
+        let (b, w) = board.count();
+        let estimates = alpha_beta.analyze(&board, side, b + w);
+        assert_eq!(estimates.len(), candidates);
+        for &(pos, score) in estimates.iter() {
+            let mut after = board.clone();
+            after.put(side, pos);
+            assert_eq!(score, Estimate(-minimax(&after, side.flip(), false) * DISC));
+        }
+        assert!(estimates.windows(2).all(|w| w[0].1 >= w[1].1));
+    }
+}
+
+#[test]
+fn stopped_analyze_returns_only_searched_moves() {
+    let (board, side, _) = random_positions(5, 1, 20).pop().unwrap();
+    let (b, w) = board.count();
+    let candidates = board.candidates(side).len();
+    assert!(candidates > 2);
+    let mut full = AlphaBetaSearch::new(SimpleEvaluator, Limit::Depth(1));
+    let depth1 = full.analyze(&board, side, b + w);
+    assert_eq!(depth1.len(), candidates);
+
+    let stop = StopCondition::new().with_nodes(2);
+    let mut search = AlphaBetaSearch::new(SimpleEvaluator, Limit::Depth(20)).with_stop(stop);
+    let partial = search.analyze(&board, side, b + w);
+    assert!(!partial.is_empty());
+    assert!(partial.len() < candidates);
+    for score in partial.iter() {
+        assert!(depth1.contains(score));
+    }
+}
+
+#[test]
+fn pattern_evaluator_is_symmetric_and_round_trips() {
+    let mut evaluator = PatternEvaluator::new(4);
+    let mut rng = Mcg128Xsl64::new(7);
+    for phase in 0..evaluator.phases() {
+        for w in evaluator.weights_mut(phase).iter_mut() {
+            *w = rand::Rng::gen_range(&mut rng, -1.0..1.0);
+        }
+    }
+    for (board, side, _) in random_positions(11, 10, 30) {
+        let e = evaluator.evaluate_discs(&board, side);
+        assert_ne!(e, 0.0);
+        for flag in 0..8 {
+            let d = evaluator.evaluate_discs(&board.symmetry(flag), side) - e;
+            assert!(d.abs() < 1e-3, "{} {}", flag, d);
+        }
+    }
+
+    let mut bytes = Vec::new();
+    evaluator.save(&mut bytes).unwrap();
+    assert_eq!(PatternEvaluator::load(&mut &bytes[..]).unwrap(), evaluator);
+    assert!(PatternEvaluator::load(&mut &bytes[..bytes.len() - 1]).is_err());
+    assert!(PatternEvaluator::load(&mut &b"nope"[..]).is_err());
+
+    let mut search = AlphaBetaSearch::new(&evaluator, Limit::Depth(2));
+    let board = BitBoard::new();
+    let result = search.search(
+        &board,
+        Side::Black,
+        4,
+        &mut board.candidates(Side::Black),
+        false,
+    );
+    assert!(board.candidates(Side::Black).contains(result.position));
+}
+
+#[test]
+fn pattern_phase_covers_edited_positions() {
+    let evaluator = PatternEvaluator::new(4);
+    let last = evaluator.phases() - 1;
+    assert_eq!(evaluator.phase(&BitBoard::new()), 0);
+    // 初期配置より石が少ない局面
+    let two = BitBoard::from_masks(BitBoard::position(3, 3), BitBoard::position(4, 4));
+    assert_eq!(evaluator.phase(&two), 0);
+    assert_eq!(evaluator.phase(&BitBoard::from_masks(0, 0)), 0);
+    assert_eq!(evaluator.phase(&BitBoard::from_masks(!0, 0)), last);
+    let e = evaluator.evaluate_discs(&two, Side::Black);
+    assert!(e.is_finite());
+}
+
+#[test]
+fn pattern_training_is_exact_and_deterministic() {
+    let evaluator = PatternEvaluator::new(4);
+    let samples = exact_samples(&evaluator, 10, 6, &mut Mcg128Xsl64::new(3));
+    assert!(!samples.is_empty());
+    assert!(samples.iter().all(|s| s.phase() == evaluator.phases() - 1));
+    assert_eq!(
+        samples,
+        exact_samples(&evaluator, 10, 6, &mut Mcg128Xsl64::new(3))
+    );
+
+    let fit = |seed| {
+        let mut rng = Mcg128Xsl64::new(seed);
+        let mut evaluator = PatternEvaluator::new(4);
+        let samples = exact_samples(&evaluator, 10, 6, &mut rng);
+        let mut losses = vec![evaluator.loss(&samples)];
+        for _ in 0..5 {
+            evaluator.fit_epoch(&samples, 0.002, &mut rng);
+            losses.push(evaluator.loss(&samples));
+        }
+        (evaluator, losses)
+    };
+    let (first, losses) = fit(3);
+    let (second, _) = fit(3);
+    assert_eq!(first, second);
+    assert_ne!(first, fit(4).0);
+    assert!(losses.windows(2).all(|w| w[1] < w[0]), "{:?}", losses);
+}
+
+#[test]
+fn mcts_beats_random() {
+    let mut rng = Mcg128Xsl64::new(5);
+    let mut wins = 0;
+    for _ in 0..4 {
+        let (b, w) = Game::new(
+            RandomSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap()),
+            MctsSearch::new(
+                RandomSearch::new(Mcg128Xsl64::from_rng(&mut rng).unwrap()),
+                Budget::Playouts(200),
+            )
+            .with_reuse(true),
+        )
+        .play_game();
+        if w > b {
+            wins += 1;
+        }
+    }
+    assert!(wins >= 3, "{} wins", wins);
+}
+
+#[test]
+fn mcts_reuses_tree() {
+    let mut search = MctsSearch::new(
+        RandomSearch::new(Mcg128Xsl64::new(6)),
+        Budget::Playouts(300),
+    )
+    .with_reuse(true);
+    let mut board = BitBoard::new();
+    let mut candidates = board.candidates(Side::Black);
+    let result = search.search(&board, Side::Black, 4, &mut candidates, false);
+    assert_eq!(result.nodes, 300);
+    assert!(board.candidates(Side::Black).contains(result.position));
+    let pv = result.pv.unwrap();
+    for (i, mv) in pv.iter().take(2).enumerate() {
+        if let Move::Put(pos) = *mv {
+            let side = if i == 0 { Side::Black } else { Side::White };
+            board.put(side, pos);
+        }
+    }
+    let mut candidates = board.candidates(Side::Black);
+    let result = search.search(&board, Side::Black, 6, &mut candidates, false);
+    assert!(result.nodes < 300);
+    assert_eq!(search.playouts(), 300);
+    assert!(board.candidates(Side::Black).contains(result.position));
+}
+
+#[test]
+fn mcts_searches_only_given_candidates() {
+    /// The last candidate of `side` alone
+    fn last_candidate(board: &BitBoard, side: Side) -> (u64, reversi::Candidate) {
+        let mut candidates = board.candidates(side);
+        let last = board.candidates(side).last().unwrap();
+        while candidates.len() > 1 {
+            candidates.next();
+        }
+        (last, candidates)
+    }
+    for &reuse in [false, true].iter() {
+        let mut search = MctsSearch::new(
+            RandomSearch::new(Mcg128Xsl64::new(8)),
+            Budget::Playouts(100),
+        )
+        .with_reuse(reuse);
+        let mut board = BitBoard::new();
+        let (last, mut candidates) = last_candidate(&board, Side::Black);
+        let result = search.search(&board, Side::Black, 4, &mut candidates, false);
+        assert_eq!(result.position, last);
+        assert_eq!(result.pv.unwrap()[0], Move::Put(last));
+
+        // 木を使い回しても根の子は絞り込まれる
+        let mut candidates = board.candidates(Side::Black);
+        let result = search.search(&board, Side::Black, 4, &mut candidates, false);
+        board.put(Side::Black, result.position);
+        let white = board.candidates(Side::White).next().unwrap();
+        board.put(Side::White, white);
+        let (last, mut candidates) = last_candidate(&board, Side::Black);
+        let result = search.search(&board, Side::Black, 6, &mut candidates, false);
+        assert_eq!(result.position, last);
+    }
+}
+
+#[test]
+fn stopped_searches_return_a_candidate() {
+    let (board, side, passed) = random_positions(12, 1, 20).pop().unwrap();
+
+    let mut solver = ExactSolver::new(1 << 12).with_stop(StopCondition::new().with_nodes(5000));
+    let (position, _) = solver.solve_with_candidates(&board, side, &mut board.candidates(side));
+    assert!(solver.stopped());
+    assert!(solver.nodes() <= 5001);
+    assert!(board.candidates(side).contains(position));
+    // 止まった勝敗探索は引き分けと区別できる
+    assert_eq!(solver.solve_wld(&board, side, passed), None);
+    assert!(solver.stopped());
+
+    let start = Instant::now();
+    let stop = StopCondition::new().with_time(Duration::from_millis(50));
+    let mut solver = ExactSolver::new(1 << 12).with_stop(stop.clone());
+    solver.solve(&board, side, passed);
+    assert!(solver.stopped());
+    let mut search = AlphaBetaSearch::new(SimpleEvaluator, Limit::Depth(20)).with_stop(stop);
+    let occupied = 64 - 20;
+    let result = search.search(&board, side, occupied, &mut board.candidates(side), passed);
+    assert!(board.candidates(side).contains(result.position));
+    assert!(start.elapsed() < Duration::from_secs(2));
+
+    // 一手も読み終えていなければ評価値も読み筋もない
+    let stop = StopCondition::new().with_nodes(0);
+    let mut search = AlphaBetaSearch::new(SimpleEvaluator, Limit::Depth(20)).with_stop(stop);
+    let result = search.search(&board, side, occupied, &mut board.candidates(side), passed);
+    assert_eq!(Some(result.position), board.candidates(side).next());
+    assert_eq!(result.score, None);
+    assert_eq!(result.pv, None);
+
+    let stop = StopCondition::new().with_flag(Arc::new(AtomicBool::new(true)));
+    let mut solver = ExactSolver::new(0).with_stop(stop.clone());
+    let first = board.candidates(side).next().unwrap();
+    let (position, _) = solver.solve_with_candidates(&board, side, &mut board.candidates(side));
+    assert_eq!(position, first);
+    let mut search = MctsSearch::new(
+        RandomSearch::new(Mcg128Xsl64::new(1)),
+        Budget::Playouts(1000),
+    )
+    .with_stop(stop);
+    let result = search.search(&board, side, occupied, &mut board.candidates(side), passed);
+    assert!(board.candidates(side).contains(result.position));
+    assert!(search.playouts() < 1000);
+
+    // 止めなければ従来通り解ける
+    let (board, side, passed) = random_positions(12, 1, 8).pop().unwrap();
+    let mut solver = ExactSolver::new(0).with_stop(StopCondition::new().with_nodes(1 << 40));
+    assert_eq!(
+        solver.solve(&board, side, passed),
+        search_exact(&board, side, passed)
+    );
+    assert!(!solver.stopped());
+}
+
+#[test]
+fn searcher_specs_build_and_play() {
+    for spec in ["random", "full:6", "full:6:8", "alphabeta:1", "mcts:10"].iter() {
+        let parsed: SearcherSpec = spec.parse().unwrap();
+        assert_eq!(parsed.to_string(), *spec);
+        let (b, w) = Game::new(parsed.build(1), parsed.build(2)).play_game();
+        assert!(b + w <= 64);
+    }
+    for spec in [
+        "",
+        "minimax:3",
+        "full",
+        "full:61",
+        "alphabeta:1:2",
+        "pattern:/nonexistent:2",
+    ]
+    .iter()
+    {
+        assert!(spec.parse::<SearcherSpec>().is_err(), "{}", spec);
+    }
+    // 勝敗探索は完全読みより手前から始まる
+    assert!("full:10:14".parse::<SearcherSpec>().is_ok());
+    assert!("full:14:10".parse::<SearcherSpec>().is_err());
+
+    // パスに ':' が含まれていてもよい
+    let path = std::env::temp_dir().join(format!("reversi:spec:{}.bin", std::process::id()));
+    PatternEvaluator::new(2).save_file(&path).unwrap();
+    let spec = format!("pattern:{}:3", path.display());
+    let parsed = spec.parse::<SearcherSpec>();
+    std::fs::remove_file(&path).unwrap();
+    let parsed = parsed.unwrap();
+    assert_eq!(parsed.to_string(), spec);
+    match parsed {
+        SearcherSpec::Pattern { depth, .. } => assert_eq!(depth, 3),
+        _ => panic!("{:?}", parsed),
+    }
+    assert!("pattern:3".parse::<SearcherSpec>().is_err());
+}
@@ -0,0 +1,21 @@
+use std::process::{Command, Output};
+
+/// Run the tournament binary with `args`
+fn tournament(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_tournament"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn random_openings_reach_the_endgame() {
+    // 終局間際まで打っても、パスを挟んでも開始局面が作れる
+    for plies in ["58", "59"].iter() {
+        let output = tournament(&["--games", "2", "--opening-plies", plies, "random", "random"]);
+        assert!(output.status.success(), "{:?}", output);
+    }
+    let output = tournament(&["--games", "1", "--opening-plies", "60", "random", "random"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--opening-plies"));
+}